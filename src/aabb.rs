@@ -0,0 +1,131 @@
+use crate::interval::Interval;
+use crate::mat4::Mat4;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// Axis-aligned bounding box, used to cheaply reject rays before testing the shape inside
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    pub fn new(a: Point3, b: Point3) -> Aabb {
+        Aabb {
+            min: Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    pub fn around_points(points: &[Point3]) -> Aabb {
+        let empty = Aabb {
+            min: Vec3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Vec3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        };
+        points.iter().fold(empty, |bounds, &point| bounds.union(&Aabb { min: point, max: point }))
+    }
+
+    pub fn around_sphere(center: Point3, radius: f64) -> Aabb {
+        let extent = Vec3::new(radius.abs(), radius.abs(), radius.abs());
+        Aabb { min: center - extent, max: center + extent }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Vec3::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y), self.min.z.min(other.min.z)),
+            max: Vec3::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y), self.max.z.max(other.max.z)),
+        }
+    }
+
+    pub fn corners(&self) -> [Point3; 8] {
+        let Aabb { min, max } = *self;
+        [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+            Vec3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    // Box around the transformed box; looser than the transformed shape, but still enclosing it
+    pub fn transformed(&self, matrix: &Mat4) -> Aabb {
+        Aabb::around_points(&self.corners().map(|corner| matrix.transform_point(corner)))
+    }
+
+    // Flat boxes (e.g. around a quad) are thickened slightly so the slab test stays robust
+    pub fn padded(&self) -> Aabb {
+        let delta = 1e-4;
+        let pad = |min: f64, max: f64| if max - min < delta { (min - delta / 2., max + delta / 2.) } else { (min, max) };
+        let (min_x, max_x) = pad(self.min.x, self.max.x);
+        let (min_y, max_y) = pad(self.min.y, self.max.y);
+        let (min_z, max_z) = pad(self.min.z, self.max.z);
+        Aabb { min: Vec3::new(min_x, min_y, min_z), max: Vec3::new(max_x, max_y, max_z) }
+    }
+
+    // Slab test: the part of `t_interval` the ray spends inside the box, if any
+    pub fn clip(&self, ray: &Ray, t_interval: Interval) -> Option<Interval> {
+        let mut clipped = t_interval;
+        let axes = [
+            (ray.origin.x, ray.direction.x, self.min.x, self.max.x),
+            (ray.origin.y, ray.direction.y, self.min.y, self.max.y),
+            (ray.origin.z, ray.direction.z, self.min.z, self.max.z),
+        ];
+        for (origin, direction, min, max) in axes {
+            // A ray parallel to the slab never enters or leaves it; this also keeps a ray lying in
+            // one of its planes from giving 0 * inf = NaN
+            if direction == 0. {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+            let inverse = 1. / direction;
+            let t0 = (min - origin) * inverse;
+            let t1 = (max - origin) * inverse;
+            clipped.min = clipped.min.max(t0.min(t1));
+            clipped.max = clipped.max.min(t0.max(t1));
+            if clipped.max < clipped.min {
+                return None;
+            }
+        }
+        Some(clipped)
+    }
+
+    pub fn hit(&self, ray: &Ray, t_interval: Interval) -> bool {
+        self.clip(ray, t_interval).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::Aabb;
+    use crate::interval::Interval;
+    use crate::ray::Ray;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn clip_to_box() {
+        let unit = Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.));
+        let ray = Ray::new(Vec3::new(-3., 0., 0.), Vec3::new(1., 0., 0.));
+        let clipped = unit.clip(&ray, Interval::new(0., f64::INFINITY)).unwrap();
+        assert_eq!((clipped.min, clipped.max), (2., 4.));
+
+        let above = Ray::new(Vec3::new(-3., 2., 0.), Vec3::new(1., 0., 0.));
+        assert!(!unit.hit(&above, Interval::new(0., f64::INFINITY)));
+    }
+
+    #[test]
+    fn rays_lying_in_a_face_plane_hit() {
+        let unit = Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.));
+        for y in [-1., 1.] {
+            let ray = Ray::new(Vec3::new(-3., y, 0.), Vec3::new(1., 0., 0.));
+            let clipped = unit.clip(&ray, Interval::new(0., f64::INFINITY)).unwrap();
+            assert_eq!((clipped.min, clipped.max), (2., 4.));
+        }
+    }
+}
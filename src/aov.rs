@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::color::Color;
+use crate::exr::{ExrImage, PixelType};
+use crate::hittable::Hit;
+use crate::image::Image;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// Arbitrary output variables: auxiliary buffers rendered alongside the beauty image, for
+// denoisers and compositing. All of them describe the first surface each camera ray hits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Aov {
+    Depth,      // Distance to the hit along the camera ray; infinite where nothing was hit
+    Normal,     // World-space shading normal, pointing out of the object
+    Albedo,     // Surface color from `Material::albedo`
+    MaterialId, // From the camera's `MaterialIds`; 0 is the sky and materials without an ID
+    ObjectId,   // 1 + index of the object in the world collection; 0 is the sky
+    Position,   // World-space hit location
+}
+
+impl Aov {
+    pub const ALL: [Aov; 6] = [Aov::Depth, Aov::Normal, Aov::Albedo, Aov::MaterialId, Aov::ObjectId, Aov::Position];
+
+    // Layer name in EXR files, also handy for file names
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::MaterialId => "material_id",
+            Aov::ObjectId => "object_id",
+            Aov::Position => "position",
+        }
+    }
+
+    // Pixel value from the samples of one pixel. Depth averages the samples that hit something, and
+    // IDs can't be averaged at all, so they come from the first sample. A pixel without samples
+    // reads as the sky.
+    pub(crate) fn resolve(&self, samples: &[Option<AovSample>]) -> Color {
+        let average = |value: fn(&AovSample) -> Vec3| {
+            if samples.is_empty() {
+                return Vec3::zero();
+            }
+            samples.iter().map(|sample| sample.as_ref().map_or(Vec3::zero(), value)).sum::<Vec3>() / samples.len() as f64
+        };
+        let id = |value: fn(&AovSample) -> u32| {
+            let id = samples.first().and_then(Option::as_ref).map_or(0, value);
+            Color::white() * id as f64
+        };
+        match self {
+            Aov::Depth => {
+                let depths: Vec<f64> = samples.iter().flatten().map(|sample| sample.depth).collect();
+                let depth = if depths.is_empty() { f64::INFINITY } else { depths.iter().sum::<f64>() / depths.len() as f64 };
+                Color::white() * depth
+            }
+            Aov::Normal => average(|sample| sample.normal),
+            Aov::Albedo => average(|sample| sample.albedo),
+            Aov::MaterialId => id(|sample| sample.material_id),
+            Aov::ObjectId => id(|sample| sample.object_id),
+            Aov::Position => average(|sample| sample.position),
+        }
+    }
+}
+
+// What a camera ray sees first
+pub(crate) struct AovSample {
+    depth: f64,
+    normal: Vec3,
+    albedo: Color,
+    material_id: u32,
+    object_id: u32,
+    position: Point3,
+}
+
+impl AovSample {
+    // `hit` is where `ray` first meets the world, on the object at `index`
+    pub(crate) fn new(ray: &Ray, index: usize, hit: &Hit, material_ids: &MaterialIds) -> AovSample {
+        AovSample {
+            depth: hit.t * ray.direction.length(), // Camera rays aren't normalized
+            normal: hit.shading_normal.normalize(),
+            albedo: hit.material.albedo(ray, hit),
+            material_id: material_ids.get(&hit.material),
+            object_id: index as u32 + 1,
+            position: hit.location,
+        }
+    }
+}
+
+// IDs for the material ID AOV, given to materials while the scene is built so they stay the
+// same from render to render. Materials are told apart by identity, i.e. by their `Arc`.
+#[derive(Clone, Default)]
+pub struct MaterialIds {
+    ids: HashMap<usize, u32>, // By the address of the material
+    // Holding on to the materials means an address can't be reused for a different one
+    materials: Vec<Arc<dyn Material>>,
+}
+
+impl MaterialIds {
+    pub fn new() -> MaterialIds {
+        MaterialIds::default()
+    }
+
+    // `id` should not be 0, which is what the sky and materials without an ID get
+    pub fn insert(&mut self, material: &Arc<dyn Material>, id: u32) {
+        if self.ids.insert(address(material), id).is_none() {
+            self.materials.push(material.clone());
+        }
+    }
+
+    pub fn get(&self, material: &Arc<dyn Material>) -> u32 {
+        self.ids.get(&address(material)).copied().unwrap_or(0)
+    }
+}
+
+fn address(material: &Arc<dyn Material>) -> usize {
+    Arc::as_ptr(material) as *const () as usize
+}
+
+// Everything one render produces: the beauty image and the AOVs the camera was asked for
+#[derive(Clone)]
+pub struct Frame {
+    pub beauty: Image,
+    pub aovs: Vec<(Aov, Image)>,
+}
+
+impl Frame {
+    pub fn aov(&self, aov: Aov) -> Option<&Image> {
+        self.aovs.iter().find(|(candidate, _)| *candidate == aov).map(|(_, image)| image)
+    }
+
+    // The beauty image as the main RGB channels and every AOV as a layer. Depth goes in the
+    // standard Z channel and IDs get a single channel each, while data AOVs are stored as 32-bit
+    // floats whatever `pixel_type` the colors use.
+    pub fn to_exr(&self, pixel_type: PixelType) -> ExrImage {
+        let mut exr = ExrImage::new(self.beauty.width, self.beauty.height);
+        exr.add_layer("", &self.beauty, pixel_type);
+        for (aov, image) in &self.aovs {
+            let single_channel = || image.pixels.iter().map(|pixel| pixel.x as f32).collect();
+            match aov {
+                Aov::Depth => exr.add_channel("Z", PixelType::Float, single_channel()),
+                Aov::MaterialId | Aov::ObjectId => exr.add_channel(format!("{}.id", aov.name()), PixelType::Float, single_channel()),
+                Aov::Normal | Aov::Albedo => exr.add_layer(aov.name(), image, pixel_type),
+                Aov::Position => exr.add_layer(aov.name(), image, PixelType::Float),
+            }
+        }
+        exr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::aov::{Aov, MaterialIds};
+    use crate::camera::Camera;
+    use crate::color::Color;
+    use crate::exr::PixelType;
+    use crate::hittable::{Hit, Hittable};
+    use crate::hittable_collection::HittableCollection;
+    use crate::material::{Material, Scattering};
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::layered::Layered;
+    use crate::materials::metal::Metal;
+    use crate::materials::principled::Principled;
+    use crate::materials::rough_conductor::RoughConductor;
+    use crate::materials::rough_dielectric::RoughDielectric;
+    use crate::progress::{CancellationToken, NoProgress};
+    use crate::ray::Ray;
+    use crate::sphere::Sphere;
+    use crate::vec3::{Point3, Vec3};
+
+    #[test]
+    fn first_hit_buffers_describe_the_visible_surface() {
+        let red: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.9, 0.1, 0.1)));
+        let unnamed: Arc<dyn Material> = Arc::new(Lambertian::new(Color::white()));
+        let objects: Vec<Box<dyn Hittable>> = vec![
+            Box::new(Sphere::new(Point3::new(0., 0., -100.), 1., unnamed)),
+            Box::new(Sphere::new(Point3::new(0., 0., -3.), 1., red.clone())),
+            Box::new(Sphere::new(Point3::new(3., 3., -3.), 1., red.clone())),
+        ];
+        let world = HittableCollection::from(objects);
+        let mut material_ids = MaterialIds::new();
+        material_ids.insert(&red, 7);
+        let camera = Camera::new(10, 1., 1, 4).with_aovs(&Aov::ALL).with_material_ids(material_ids);
+        let frame = camera.render_frame(&world, &mut NoProgress, &CancellationToken::new());
+
+        // The middle pixel looks straight down -z at the near sphere
+        let (x, y) = (4, 4);
+        assert!((frame.aov(Aov::Depth).unwrap().get(x, y).x - 2.).abs() < 1e-9);
+        assert!((frame.aov(Aov::Normal).unwrap().get(x, y) - Vec3::new(0., 0., 1.)).length() < 1e-9);
+        assert!((frame.aov(Aov::Position).unwrap().get(x, y) - Point3::new(0., 0., -2.)).length() < 1e-9);
+        assert_eq!(frame.aov(Aov::Albedo).unwrap().get(x, y), Color::new(0.9, 0.1, 0.1));
+        assert_eq!(frame.aov(Aov::ObjectId).unwrap().get(x, y).x, 2.);
+        assert_eq!(frame.aov(Aov::MaterialId).unwrap().get(x, y).x, 7.);
+
+        // The corner sees only sky
+        assert_eq!(frame.aov(Aov::Depth).unwrap().get(0, 0).x, f64::INFINITY);
+        assert_eq!(frame.aov(Aov::ObjectId).unwrap().get(0, 0).x, 0.);
+        assert_eq!(frame.aov(Aov::Normal).unwrap().get(0, 0), Vec3::zero());
+    }
+
+    #[test]
+    fn only_selected_aovs_are_rendered() {
+        let world = HittableCollection::new();
+        let camera = Camera::new(4, 1., 1, 2);
+        assert!(camera.render_frame(&world, &mut NoProgress, &CancellationToken::new()).aovs.is_empty());
+
+        let camera = camera.with_aovs(&[Aov::Albedo, Aov::Depth]);
+        let frame = camera.render_frame(&world, &mut NoProgress, &CancellationToken::new());
+        let rendered: Vec<Aov> = frame.aovs.iter().map(|(aov, _)| *aov).collect();
+        assert_eq!(rendered, [Aov::Albedo, Aov::Depth]);
+
+        let names: Vec<String> = frame.to_exr(PixelType::Half).channels.iter().map(|channel| channel.name.clone()).collect();
+        assert_eq!(names, ["R", "G", "B", "albedo.R", "albedo.G", "albedo.B", "Z"]);
+    }
+
+    #[test]
+    fn pixels_without_samples_read_as_sky() {
+        let world = HittableCollection::new();
+        let camera = Camera::new(2, 1., 0, 2).with_aovs(&Aov::ALL);
+        let frame = camera.render_frame(&world, &mut NoProgress, &CancellationToken::new());
+
+        assert_eq!(frame.aov(Aov::Depth).unwrap().get(0, 0).x, f64::INFINITY);
+        assert_eq!(frame.aov(Aov::Normal).unwrap().get(0, 0), Vec3::zero());
+        assert_eq!(frame.aov(Aov::MaterialId).unwrap().get(0, 0).x, 0.);
+    }
+
+    #[test]
+    fn albedo_is_the_same_every_time() {
+        let lambertian: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.9, 0.1, 0.1)));
+        let materials: Vec<Arc<dyn Material>> = vec![
+            lambertian.clone(),
+            Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.5)),
+            Arc::new(RoughConductor::gold(0.4)),
+            Arc::new(RoughDielectric::new(1.5, 0.3)),
+            Arc::new(Layered::new(lambertian, 1.5, Color::new(0.9, 0.9, 0.5))),
+            Arc::new(Principled::new(Color::new(0.2, 0.4, 0.6))),
+        ];
+        let ray = Ray::new(Point3::new(0., 1., 0.), Vec3::new(0.3, -1., 0.));
+        for material in materials {
+            let hit = Hit::new(&ray, 1., Vec3::new(0., 1., 0.), 0., 0., material.clone());
+            let first = material.albedo(&ray, &hit);
+            for _ in 0..20 {
+                assert_eq!(material.albedo(&ray, &hit), first);
+            }
+        }
+    }
+
+    #[test]
+    fn materials_without_an_albedo_read_as_black() {
+        struct Absorber;
+        impl Material for Absorber {
+            fn scatter(&self, _ray: &Ray, _hit: &Hit) -> Option<Scattering> {
+                None
+            }
+        }
+        let ray = Ray::new(Point3::new(0., 1., 0.), Vec3::new(0., -1., 0.));
+        let hit = Hit::new(&ray, 1., Vec3::new(0., 1., 0.), 0., 0., Arc::new(Absorber));
+        assert_eq!(Absorber.albedo(&ray, &hit), Color::black());
+    }
+}
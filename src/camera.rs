@@ -1,8 +1,14 @@
-use crate::color::{write_color, Color};
-use crate::hittable::Hittable;
+use std::time::Instant;
+use rand::{rng, Rng};
+use crate::aov::{Aov, AovSample, Frame, MaterialIds};
+use crate::color::Color;
+use crate::hittable::{Hit, Hittable};
 use crate::hittable_collection::HittableCollection;
+use crate::image::Image;
 use crate::interval::Interval;
+use crate::progress::{CancellationToken, Progress, ProgressObserver};
 use crate::ray::Ray;
+use crate::spectrum::{SampledSpectrum, SampledWavelengths};
 use crate::vec3::{Point3, Vec3};
 
 pub struct Camera {
@@ -14,6 +20,10 @@ pub struct Camera {
     pixel_delta_v: Vec3,     // Offset to pixel below
     msaa_level: u8,          // Count of rows and columns of rays we should cast per pixel
     max_light_bounces: u8,           // Maximum number of ray bounces
+    shutter: Interval,       // Times the shutter opens and closes; rays are spread over it for motion blur
+    spectral: bool,          // Trace sampled wavelengths instead of RGB
+    aovs: Vec<Aov>,          // Auxiliary buffers to render besides the beauty image
+    material_ids: MaterialIds, // What the material ID AOV reports
 }
 
 impl Camera {
@@ -52,93 +62,401 @@ impl Camera {
             pixel_delta_v,
             msaa_level,
             max_light_bounces,
+            shutter: Interval::new(0., 0.),
+            spectral: false,
+            aovs: Vec::new(),
+            material_ids: MaterialIds::new(),
         }
     }
 
-    pub fn render(&self, world: &HittableCollection) {
+    pub fn with_shutter(mut self, shutter: Interval) -> Camera {
+        self.shutter = shutter;
+        self
+    }
+
+    // In spectral mode every path carries a few wavelengths (see `SampledWavelengths`), so
+    // wavelength-dependent materials like measured metals and dispersive glass render correctly.
+    // Colors elsewhere in the scene are upsampled from RGB, and each sample is converted back to
+    // RGB before it reaches the image.
+    pub fn with_spectral(mut self, spectral: bool) -> Camera {
+        self.spectral = spectral;
+        self
+    }
+
+    // AOVs that `render_frame` produces, in this order
+    pub fn with_aovs(mut self, aovs: &[Aov]) -> Camera {
+        self.aovs = aovs.to_vec();
+        self
+    }
+
+    pub fn with_material_ids(mut self, material_ids: MaterialIds) -> Camera {
+        self.material_ids = material_ids;
+        self
+    }
+
+    // Renders row by row, reporting to `observer` after every row.
+    // If `cancellation` fires, the rows rendered so far are kept and the rest stay black.
+    pub fn render(
+        &self,
+        world: &HittableCollection,
+        observer: &mut impl ProgressObserver,
+        cancellation: &CancellationToken,
+    ) -> Image {
+        self.render_frame(world, observer, cancellation).beauty
+    }
+
+    // `render`, along with the AOVs selected by `with_aovs`
+    pub fn render_frame(
+        &self,
+        world: &HittableCollection,
+        observer: &mut impl ProgressObserver,
+        cancellation: &CancellationToken,
+    ) -> Frame {
         let image_width = self.image_width;
         let image_height = self.image_height;
-        println!("P3");
-        println!("{image_width} {image_height}");
-        println!("255");
+        let mut image = Image::new(image_width, image_height);
+        let mut aovs: Vec<(Aov, Image)> = self.aovs.iter().map(|&aov| (aov, Image::new(image_width, image_height))).collect();
 
+        let start = Instant::now();
+        let mut rays_traced = 0;
         let pixel_color_scale = 1. / ((self.msaa_level as f64) * (self.msaa_level as f64));
         for y in 0..image_height {
+            if cancellation.is_cancelled() {
+                break;
+            }
             for x in 0..image_width {
-                let pixel_color: Vec3 = self
-                    .rays_from_pixel(x, y, self.msaa_level)
+                let rays = self.rays_from_pixel(x, y, self.msaa_level);
+                let mut samples: Vec<Option<AovSample>> = Vec::new();
+                let pixel_color: Vec3 = rays
                     .iter()
-                    .map(|ray| self.color_from_ray(ray, world, self.max_light_bounces))
+                    .map(|ray| {
+                        if aovs.is_empty() {
+                            return self.sample(ray, world, &mut rays_traced, None);
+                        }
+                        let mut first_hit = None;
+                        let color = self.sample(ray, world, &mut rays_traced, Some(&mut first_hit));
+                        samples.push(first_hit);
+                        color
+                    })
                     .sum();
 
-                write_color(pixel_color * pixel_color_scale);
+                image.set(x, y, pixel_color * pixel_color_scale);
+
+                if !aovs.is_empty() {
+                    for (aov, buffer) in &mut aovs {
+                        buffer.set(x, y, aov.resolve(&samples));
+                    }
+                }
             }
+            observer.on_progress(&Progress {
+                completed_rows: y as u64 + 1,
+                total_rows: image_height as u64,
+                elapsed: start.elapsed(),
+                rays_traced,
+            });
         }
+        Frame { beauty: image, aovs }
     }
 
-    fn rays_from_pixel(&self, x: u16, y: u16, msaa_level: u8) -> Vec<Ray> {
-        let mut points: Vec<(f64, f64)> = Vec::new();
-        for y in 1..=msaa_level {
-            for x in 1..=msaa_level {
-                let x = x as f64;
-                let y = y as f64;
-                let n = msaa_level as f64;
-                points.push((x / n - 1. / (2. * n), y / n - 1. / (2. * n)));
+    // Renders the image `passes` times over with one sample per pixel each, so a usable picture
+    // appears early and then sharpens. After every pass `on_pass` gets the pass count so far and the
+    // average of those passes. Each pass takes a random point in the next of the msaa_level²
+    // cells `render` samples the pixel at, so after msaa_level² passes the result is like `render`.
+    // If `cancellation` fires, the rows the unfinished pass already covered keep their extra sample.
+    pub fn render_progressive(
+        &self,
+        world: &HittableCollection,
+        passes: u32,
+        observer: &mut impl ProgressObserver,
+        cancellation: &CancellationToken,
+        on_pass: &mut impl FnMut(u32, &Image),
+    ) -> Image {
+        let image_width = self.image_width;
+        let image_height = self.image_height;
+        let mut sum = Image::new(image_width, image_height);
+        // Without MSAA every pass samples around the pixel center
+        let msaa_level = self.msaa_level.max(1);
+        let offsets = sample_offsets(msaa_level);
+        let cell_size = 1. / msaa_level as f64;
+
+        let start = Instant::now();
+        let mut rays_traced = 0;
+        for pass in 0..passes {
+            let (x_center, y_center) = offsets[pass as usize % offsets.len()];
+            for y in 0..image_height {
+                if cancellation.is_cancelled() {
+                    return average_passes(&sum, pass, y);
+                }
+                for x in 0..image_width {
+                    let offset = (
+                        x_center + (rng().random::<f64>() - 0.5) * cell_size,
+                        y_center + (rng().random::<f64>() - 0.5) * cell_size,
+                    );
+                    let ray = self.ray_through(x, y, offset);
+                    let color = self.sample(&ray, world, &mut rays_traced, None);
+                    sum.set(x, y, sum.get(x, y) + color);
+                }
+                observer.on_progress(&Progress {
+                    completed_rows: pass as u64 * image_height as u64 + y as u64 + 1,
+                    total_rows: passes as u64 * image_height as u64,
+                    elapsed: start.elapsed(),
+                    rays_traced,
+                });
             }
+            on_pass(pass + 1, &average_passes(&sum, pass + 1, 0));
         }
-        points
-            .iter()
-            .map(|(x_offset, y_offset)| {
-                let pixel_sample = self.first_pixel_loc
-                    + ((x as f64 + x_offset) * self.pixel_delta_u)
-                    + ((y as f64 + y_offset) * self.pixel_delta_v);
-
-                let ray_origin = self.center;
-                let ray_direction = pixel_sample - ray_origin;
-                Ray {
-                    origin: ray_origin,
-                    direction: ray_direction,
-                }
-            })
+        average_passes(&sum, passes, 0)
+    }
+
+    fn rays_from_pixel(&self, x: u16, y: u16, msaa_level: u8) -> Vec<Ray> {
+        sample_offsets(msaa_level)
+            .into_iter()
+            .map(|offset| self.ray_through(x, y, offset))
             .collect()
     }
 
-    fn color_from_ray(&self, ray: &Ray, world: &HittableCollection, remaining_bounces: u8) -> Color {
-        if remaining_bounces <= 0 {
+    // Ray through pixel (x, y) at `offset` from its top left corner, in pixels
+    fn ray_through(&self, x: u16, y: u16, (x_offset, y_offset): (f64, f64)) -> Ray {
+        let pixel_sample = self.first_pixel_loc
+            + ((x as f64 + x_offset) * self.pixel_delta_u)
+            + ((y as f64 + y_offset) * self.pixel_delta_v);
+
+        let ray_origin = self.center;
+        let ray_direction = pixel_sample - ray_origin;
+        let ray_time = if self.shutter.size() > 0. {
+            rng().random_range(self.shutter.min..self.shutter.max)
+        } else {
+            self.shutter.min
+        };
+        Ray::with_time(ray_origin, ray_direction, ray_time)
+    }
+
+    // Color seen along a camera ray. If `first_hit` is given, it also gets what the ray hits first,
+    // for the AOVs, so they don't need an intersection of their own.
+    fn sample(
+        &self,
+        ray: &Ray,
+        world: &HittableCollection,
+        rays_traced: &mut u64,
+        first_hit: Option<&mut Option<AovSample>>,
+    ) -> Color {
+        let hit = world.hit_object(ray, Interval::new(0.001, f64::INFINITY));
+        if let Some(first_hit) = first_hit {
+            *first_hit = hit.as_ref().map(|(index, hit)| AovSample::new(ray, *index, hit, &self.material_ids));
+        }
+        if self.max_light_bounces == 0 {
             return Color::black();
         }
+        *rays_traced += 1;
+        let hit = hit.map(|(_, hit)| hit);
+        if !self.spectral {
+            return self.color_from_hit(ray, hit, world, self.max_light_bounces, rays_traced);
+        }
+        let mut wavelengths = SampledWavelengths::sample_uniform(rng().random());
+        let radiance = self.spectrum_from_hit(ray, hit, world, self.max_light_bounces, &mut wavelengths, rays_traced);
+        radiance.to_rgb(&wavelengths)
+    }
+
+    fn color_from_ray(&self, ray: &Ray, world: &HittableCollection, remaining_bounces: u8, rays_traced: &mut u64) -> Color {
+        if remaining_bounces == 0 {
+            return Color::black();
+        }
+        *rays_traced += 1;
         let hit = world.hit(
-            &ray,
+            ray,
             Interval {
                 min: 0.001,
                 max: f64::INFINITY,
             },
         );
+        self.color_from_hit(ray, hit, world, remaining_bounces, rays_traced)
+    }
+
+    // The rest of `color_from_ray`, once `ray` has been intersected with the world
+    fn color_from_hit(
+        &self,
+        ray: &Ray,
+        hit: Option<Hit>,
+        world: &HittableCollection,
+        remaining_bounces: u8,
+        rays_traced: &mut u64,
+    ) -> Color {
         match hit {
             Some(hit) => {
-                match hit.material.scatter(&ray, &hit) {
-                    None => Color::black(),
+                let emitted = hit.material.emitted(ray, &hit);
+                match hit.material.scatter(ray, &hit) {
+                    None => emitted,
                     Some(scattering) =>
-                        scattering.attenuation * self.color_from_ray(&scattering.scattered, world, remaining_bounces-1),
+                        emitted + scattering.attenuation * self.color_from_ray(&scattering.scattered, world, remaining_bounces-1, rays_traced),
                 }
             }
-            None => {
-                let unit_direction = ray.direction.normalize();
-                let a = 0.5 * (unit_direction.y + 1.0);
-                lerp(
-                    a,
-                    Color {
-                        x: 0.5,
-                        y: 0.7,
-                        z: 1.0,
-                    },
-                    Color::white(),
-                )
+            None => sky_color(ray),
+        }
+    }
+
+    fn spectrum_from_ray(
+        &self,
+        ray: &Ray,
+        world: &HittableCollection,
+        remaining_bounces: u8,
+        wavelengths: &mut SampledWavelengths,
+        rays_traced: &mut u64,
+    ) -> SampledSpectrum {
+        if remaining_bounces == 0 {
+            return SampledSpectrum::constant(0.);
+        }
+        *rays_traced += 1;
+        let hit = world.hit(ray, Interval::new(0.001, f64::INFINITY));
+        self.spectrum_from_hit(ray, hit, world, remaining_bounces, wavelengths, rays_traced)
+    }
+
+    // The rest of `spectrum_from_ray`, once `ray` has been intersected with the world
+    fn spectrum_from_hit(
+        &self,
+        ray: &Ray,
+        hit: Option<Hit>,
+        world: &HittableCollection,
+        remaining_bounces: u8,
+        wavelengths: &mut SampledWavelengths,
+        rays_traced: &mut u64,
+    ) -> SampledSpectrum {
+        let Some(hit) = hit else {
+            return SampledSpectrum::from_rgb(sky_color(ray), wavelengths);
+        };
+        // Scatter first: a dispersive material may drop all but the hero wavelength, and the light
+        // emitted here has to be counted the same way
+        let scattering = hit.material.scatter_spectral(ray, &hit, wavelengths);
+        let emitted = SampledSpectrum::from_rgb(hit.material.emitted(ray, &hit), wavelengths);
+        match scattering {
+            None => emitted,
+            Some(scattering) => {
+                let incoming = self.spectrum_from_ray(&scattering.scattered, world, remaining_bounces - 1, wavelengths, rays_traced);
+                emitted + scattering.attenuation * incoming
             }
         }
     }
 }
 
+// Where in a pixel its msaa_level x msaa_level samples go, row by row
+fn sample_offsets(msaa_level: u8) -> Vec<(f64, f64)> {
+    let mut points: Vec<(f64, f64)> = Vec::new();
+    for y in 1..=msaa_level {
+        for x in 1..=msaa_level {
+            let x = x as f64;
+            let y = y as f64;
+            let n = msaa_level as f64;
+            points.push((x / n - 1. / (2. * n), y / n - 1. / (2. * n)));
+        }
+    }
+    points
+}
+
+// Average of a progressive render's sample sums, where the first `partial_rows` rows have been
+// through one more pass than the rest
+fn average_passes(sum: &Image, complete_passes: u32, partial_rows: u16) -> Image {
+    let mut average = sum.clone();
+    for y in 0..sum.height {
+        let passes = complete_passes + (y < partial_rows) as u32;
+        if passes == 0 {
+            continue;
+        }
+        for x in 0..sum.width {
+            average.set(x, y, sum.get(x, y) / passes as f64);
+        }
+    }
+    average
+}
+
+fn sky_color(ray: &Ray) -> Color {
+    let unit_direction = ray.direction.normalize();
+    let a = 0.5 * (unit_direction.y + 1.0);
+    lerp(
+        a,
+        Color {
+            x: 0.5,
+            y: 0.7,
+            z: 1.0,
+        },
+        Color::white(),
+    )
+}
+
 fn lerp(factor: f64, start: Vec3, end: Vec3) -> Vec3 {
     (1.0 - factor) * end + factor * start
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::Camera;
+    use crate::color::Color;
+    use crate::hittable_collection::HittableCollection;
+    use crate::image::Image;
+    use crate::progress::{CancellationToken, NoProgress, Progress};
+
+    #[test]
+    fn cancelled_render_keeps_finished_rows() {
+        let camera = Camera::new(8, 2., 1, 2);
+        let world = HittableCollection::new();
+        let cancellation = CancellationToken::new();
+        let mut cancel_after_first_row = |_: &Progress| cancellation.cancel();
+
+        let image = camera.render(&world, &mut cancel_after_first_row, &cancellation);
+
+        assert_ne!(image.get(0, 0), Color::black());
+        assert_eq!(image.get(0, 1), Color::black());
+    }
+
+    #[test]
+    fn spectral_render_of_the_sky_matches_rgb() {
+        let camera = Camera::new(4, 1., 32, 2);
+        let world = HittableCollection::new();
+        let rgb = camera.render(&world, &mut NoProgress, &CancellationToken::new());
+        let spectral = camera.with_spectral(true).render(&world, &mut NoProgress, &CancellationToken::new());
+
+        let average = |image: &Image| image.pixels.iter().copied().sum::<Color>() / image.pixels.len() as f64;
+        assert!((average(&rgb) - average(&spectral)).length() < 0.03);
+    }
+
+    #[test]
+    fn progressive_render_ends_up_like_a_normal_one() {
+        // The sky alone is smooth, so jittering the samples within their cells barely changes it
+        let camera = Camera::new(6, 1.5, 3, 2);
+        let world = HittableCollection::new();
+        let mut passes = Vec::new();
+        let mut record_pass = |pass: u32, image: &Image| passes.push((pass, image.get(0, 0)));
+        let progressive = camera.render_progressive(&world, 9, &mut NoProgress, &CancellationToken::new(), &mut record_pass);
+        let normal = camera.render(&world, &mut NoProgress, &CancellationToken::new());
+
+        assert_eq!(passes.iter().map(|(pass, _)| *pass).collect::<Vec<_>>(), (1..=9).collect::<Vec<_>>());
+        assert_ne!(passes[0].1, passes[8].1);
+        for (a, b) in progressive.pixels.iter().zip(&normal.pixels) {
+            assert!((a - b).length() < 0.02);
+        }
+    }
+
+    #[test]
+    fn progressive_passes_do_not_depend_on_msaa_level() {
+        let camera = Camera::new(4, 1., 1, 2);
+        let world = HittableCollection::new();
+        let mut passes = Vec::new();
+        let mut record_pass = |pass: u32, image: &Image| passes.push((pass, image.get(0, 0)));
+        camera.render_progressive(&world, 5, &mut NoProgress, &CancellationToken::new(), &mut record_pass);
+
+        assert_eq!(passes.iter().map(|(pass, _)| *pass).collect::<Vec<_>>(), (1..=5).collect::<Vec<_>>());
+        // Every pass samples somewhere else in the pixel
+        assert_ne!(passes[0].1, passes[4].1);
+    }
+
+    #[test]
+    fn progressive_render_without_msaa_samples_pixel_centers() {
+        let camera = Camera::new(4, 1., 0, 2);
+        let world = HittableCollection::new();
+        let mut last_progress = None;
+        let mut record_progress = |progress: &Progress| last_progress = Some(*progress);
+        let image = camera.render_progressive(&world, 3, &mut record_progress, &CancellationToken::new(), &mut |_, _| {});
+
+        assert!(image.pixels.iter().all(|pixel| *pixel != Color::black()));
+        let last_progress = last_progress.unwrap();
+        assert_eq!((last_progress.completed_rows, last_progress.total_rows), (12, 12));
+    }
+}
@@ -1,3 +1,5 @@
+use std::io;
+use std::io::Write;
 use crate::vec3::Vec3;
 
 pub type Color = Vec3;
@@ -34,29 +36,26 @@ impl Color {
     pub fn black() -> Color {
         Vec3::zero()
     }
-    
-    pub fn gamma_correct(&self) -> Color {
-        self.map(linear_to_gamma)
-    }
 }
 
-pub fn write_color(color: Color) {
-    
-    let color = color
-        .gamma_correct()
-        .clamp(0., 0.999);
-
-    let color = color * 256.;
+// Writes one PPM pixel, already encoded for display
+pub fn write_color(out: &mut impl Write, [r, g, b]: [u8; 3]) -> io::Result<()> {
+    writeln!(out, "{r} {g} {b}")
+}
 
-    let Color { x: r, y: g, z: b } = color;
-    let (r, g, b) = (r as u8, g as u8, b as u8);
-    println!("{r} {g} {b}")
+// The sRGB transfer function: linear light in [0, 1] to the encoded value displays expect
+pub fn linear_to_srgb(linear: f64) -> f64 {
+    if linear <= 0.0031308 {
+        12.92 * linear.max(0.)
+    } else {
+        1.055 * linear.powf(1. / 2.4) - 0.055
+    }
 }
 
-pub fn linear_to_gamma(linear_component: f64) -> f64 {
-    if linear_component >= 0. {
-        linear_component.sqrt()
+pub fn srgb_to_linear(encoded: f64) -> f64 {
+    if encoded <= 0.04045 {
+        encoded.max(0.) / 12.92
     } else {
-        0.
+        ((encoded + 0.055) / 1.055).powf(2.4)
     }
 }
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::ray::Ray;
+use crate::solid::{Solid, Span};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(&self, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => inside_left || inside_right,
+            CsgOperation::Intersection => inside_left && inside_right,
+            CsgOperation::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+// Combination of two solids. Each surface of the result keeps the material of the operand it
+// came from; surfaces cut by the right operand of a difference face into the hole it leaves.
+#[derive(Clone)]
+pub struct Csg {
+    pub operation: CsgOperation,
+    pub left: Arc<dyn Solid>,
+    pub right: Arc<dyn Solid>,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Arc<dyn Solid>, right: Arc<dyn Solid>) -> Csg {
+        Csg { operation, left, right }
+    }
+
+    pub fn union(left: Arc<dyn Solid>, right: Arc<dyn Solid>) -> Csg {
+        Csg::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: Arc<dyn Solid>, right: Arc<dyn Solid>) -> Csg {
+        Csg::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference(left: Arc<dyn Solid>, right: Arc<dyn Solid>) -> Csg {
+        Csg::new(CsgOperation::Difference, left, right)
+    }
+}
+
+struct Boundary {
+    hit: Hit,
+    from_left: bool,
+    entering: bool,
+}
+
+fn boundaries(spans: Vec<Span>, from_left: bool) -> impl Iterator<Item = Boundary> {
+    spans.into_iter().flat_map(move |span| {
+        [
+            Boundary { hit: span.enter, from_left, entering: true },
+            Boundary { hit: span.exit, from_left, entering: false },
+        ]
+    })
+}
+
+impl Solid for Csg {
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        let mut events: Vec<Boundary> = boundaries(self.left.spans(ray), true)
+            .chain(boundaries(self.right.spans(ray), false))
+            .collect();
+        events.sort_by(|a, b| a.hit.t.total_cmp(&b.hit.t));
+
+        // Sweep along the ray, emitting a boundary every time the combined inside/outside state flips
+        let mut inside_left = false;
+        let mut inside_right = false;
+        let mut enter: Option<Hit> = None;
+        let mut spans = Vec::new();
+        for Boundary { mut hit, from_left, entering } in events {
+            if from_left {
+                inside_left = entering;
+            } else {
+                inside_right = entering;
+            }
+            if !from_left && self.operation == CsgOperation::Difference {
+                hit.flip();
+            }
+
+            let inside = self.operation.contains(inside_left, inside_right);
+            match enter.take() {
+                None if inside => enter = Some(hit),
+                None => {}
+                Some(enter_hit) if !inside => spans.push(Span { enter: enter_hit, exit: hit }),
+                Some(enter_hit) => enter = Some(enter_hit),
+            }
+        }
+        spans
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        self.spans(ray)
+            .into_iter()
+            .flat_map(|span| [span.enter, span.exit])
+            .find(|hit| t_interval.surrounds(hit.t))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // Intersections and differences can't extend beyond their left operand
+        match self.operation {
+            CsgOperation::Union => Some(self.left.bounding_box()?.union(&self.right.bounding_box()?)),
+            CsgOperation::Intersection | CsgOperation::Difference => self.left.bounding_box(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::csg::Csg;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::cylinder::Cylinder;
+    use crate::sphere::Sphere;
+    use crate::vec3::Vec3;
+
+    // Unit sphere with a hole of radius 0.25 drilled along the y axis
+    fn drilled_sphere() -> Csg {
+        let sphere = Sphere::new(Vec3::zero(), 1., Arc::new(Lambertian::new(Color::white())));
+        let drill = Cylinder::new(
+            Vec3::new(0., -2., 0.),
+            Vec3::new(0., 1., 0.),
+            0.25,
+            4.,
+            false,
+            Arc::new(Lambertian::new(Color::red())),
+        );
+        Csg::difference(Arc::new(sphere), Arc::new(drill))
+    }
+
+    #[test]
+    fn ray_down_the_hole_passes_through() {
+        let ray = Ray::new(Vec3::new(0., 5., 0.), Vec3::new(0., -1., 0.));
+        assert!(drilled_sphere().hit(&ray, Interval::new(0.001, f64::INFINITY)).is_none());
+    }
+
+    #[test]
+    fn wall_of_the_hole_faces_into_the_hole_with_the_drill_material() {
+        // Starts inside the hole and looks sideways into the wall
+        let ray = Ray::new(Vec3::zero(), Vec3::new(1., 0., 0.));
+        let hit = drilled_sphere().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 0.25).abs() < 1e-9);
+        assert!((hit.normal - Vec3::new(-1., 0., 0.)).length() < 1e-9);
+
+        let scattering = hit.material.scatter(&ray, &hit).unwrap();
+        assert_eq!(scattering.attenuation, Color::red());
+    }
+
+    #[test]
+    fn intersection_of_overlapping_spheres_is_a_lens() {
+        let material = Arc::new(Lambertian::new(Color::white()));
+        let left = Sphere::new(Vec3::new(-0.5, 0., 0.), 1., material.clone());
+        let right = Sphere::new(Vec3::new(0.5, 0., 0.), 1., material);
+        let lens = Csg::intersection(Arc::new(left), Arc::new(right));
+
+        let ray = Ray::new(Vec3::new(-5., 0., 0.), Vec3::new(1., 0., 0.));
+        let hit = lens.hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.location.x - -0.5).abs() < 1e-9);
+        assert!((hit.normal - Vec3::new(-1., 0., 0.)).length() < 1e-9);
+    }
+}
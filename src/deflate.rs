@@ -0,0 +1,241 @@
+// Minimal zlib (RFC 1950/1951) compressor for the EXR writer: LZ77 with hash chains, encoded in a
+// single block with the fixed Huffman codes. It compresses noticeably worse than zlib with dynamic
+// codes, but every zlib reader can decompress it.
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64; // Candidates tried per position; more compresses better but slower
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASE: [u16; 29] =
+    [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+pub fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut bits = BitWriter::default();
+    // Deflate with a 32K window and no preset dictionary; the check bits make the header a multiple of 31
+    bits.bytes.extend_from_slice(&[0x78, 0x01]);
+    bits.write(1, 1); // Final block
+    bits.write(0b01, 2); // Fixed Huffman codes
+
+    let mut chains = HashChains::new(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let (length, distance) = chains.longest_match(data, i);
+        if length >= MIN_MATCH {
+            write_match(&mut bits, length, distance);
+            for j in i..i + length {
+                chains.insert(data, j);
+            }
+            i += length;
+        } else {
+            write_literal_or_length(&mut bits, data[i] as u16);
+            chains.insert(data, i);
+            i += 1;
+        }
+    }
+    write_literal_or_length(&mut bits, 256); // End of block
+
+    let mut out = bits.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+// Earlier positions starting with the same three bytes, newest first
+struct HashChains {
+    head: Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl HashChains {
+    fn new(length: usize) -> HashChains {
+        HashChains { head: vec![usize::MAX; 1 << HASH_BITS], previous: vec![usize::MAX; length] }
+    }
+
+    fn hash(data: &[u8], i: usize) -> usize {
+        let key = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+        (key.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn insert(&mut self, data: &[u8], i: usize) {
+        if i + MIN_MATCH <= data.len() {
+            let hash = HashChains::hash(data, i);
+            self.previous[i] = self.head[hash];
+            self.head[hash] = i;
+        }
+    }
+
+    // Length and distance of the longest earlier match for the bytes at `i`
+    fn longest_match(&self, data: &[u8], i: usize) -> (usize, usize) {
+        if i + MIN_MATCH > data.len() {
+            return (0, 0);
+        }
+        let max_length = MAX_MATCH.min(data.len() - i);
+        let (mut best_length, mut best_distance) = (0, 0);
+        let mut candidate = self.head[HashChains::hash(data, i)];
+        for _ in 0..MAX_CHAIN {
+            if candidate == usize::MAX || i - candidate > WINDOW_SIZE {
+                break;
+            }
+            let length = data[candidate..].iter().zip(&data[i..i + max_length]).take_while(|(a, b)| a == b).count();
+            if length > best_length {
+                (best_length, best_distance) = (length, i - candidate);
+                if length == max_length {
+                    break;
+                }
+            }
+            candidate = self.previous[candidate];
+        }
+        (best_length, best_distance)
+    }
+}
+
+fn write_match(bits: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE.partition_point(|&base| base as usize <= length) - 1;
+    write_literal_or_length(bits, 257 + code as u16);
+    bits.write((length - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code]);
+
+    let code = DISTANCE_BASE.partition_point(|&base| base as usize <= distance) - 1;
+    bits.write_code(code as u32, 5);
+    bits.write((distance - DISTANCE_BASE[code] as usize) as u32, DISTANCE_EXTRA[code]);
+}
+
+// Fixed Huffman code of a literal byte, end of block (256) or length symbol (257 to 285)
+fn write_literal_or_length(bits: &mut BitWriter, symbol: u16) {
+    let symbol = symbol as u32;
+    match symbol {
+        0..=143 => bits.write_code(0x30 + symbol, 8),
+        144..=255 => bits.write_code(0x190 + symbol - 144, 9),
+        256..=279 => bits.write_code(symbol - 256, 7),
+        _ => bits.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before b could overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+// Packs bits least significant first, as deflate wants
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    count: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bit_count: u8) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += bit_count;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes are the one thing stored most significant bit first
+    fn write_code(&mut self, code: u32, bit_count: u8) {
+        self.write(code.reverse_bits() >> (32 - bit_count), bit_count);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::deflate::{adler32, zlib_compress, DISTANCE_BASE, DISTANCE_EXTRA, LENGTH_BASE, LENGTH_EXTRA};
+
+    // Decompresses the subset of zlib `zlib_compress` produces (fixed Huffman blocks only)
+    pub(crate) fn zlib_decompress(compressed: &[u8]) -> Vec<u8> {
+        assert_eq!(compressed[0] & 0x0f, 8, "not deflate");
+        assert_eq!((compressed[0] as u32 * 256 + compressed[1] as u32) % 31, 0);
+        let mut position = 16; // In bits
+        let mut bit = || {
+            let value = (compressed[position / 8] >> (position % 8)) & 1;
+            position += 1;
+            value as u32
+        };
+        let mut out: Vec<u8> = Vec::new();
+        loop {
+            let last = bit();
+            assert_eq!(bit() | bit() << 1, 1, "only fixed Huffman blocks are supported");
+            loop {
+                let mut read = |count: u8| (0..count).fold(0, |value, i| value | bit() << i);
+                let mut code = 0;
+                for _ in 0..7 {
+                    code = code << 1 | read(1);
+                }
+                let symbol = if code <= 0x17 {
+                    code + 256
+                } else {
+                    code = code << 1 | read(1);
+                    match code {
+                        0x30..=0xbf => code - 0x30,
+                        0xc0..=0xc7 => code - 0xc0 + 280,
+                        _ => (code << 1 | read(1)) - 0x190 + 144,
+                    }
+                };
+                match symbol {
+                    0..=255 => out.push(symbol as u8),
+                    256 => break,
+                    _ => {
+                        let index = (symbol - 257) as usize;
+                        let length = LENGTH_BASE[index] as usize + read(LENGTH_EXTRA[index]) as usize;
+                        let mut distance_code = 0;
+                        for _ in 0..5 {
+                            distance_code = distance_code << 1 | read(1);
+                        }
+                        let index = distance_code as usize;
+                        let distance = DISTANCE_BASE[index] as usize + read(DISTANCE_EXTRA[index]) as usize;
+                        for _ in 0..length {
+                            out.push(out[out.len() - distance]);
+                        }
+                    }
+                }
+            }
+            if last == 1 {
+                break;
+            }
+        }
+        let checksum = &compressed[compressed.len() - 4..];
+        assert_eq!(checksum, adler32(&out).to_be_bytes());
+        out
+    }
+
+    #[test]
+    fn round_trip() {
+        let repetitive: Vec<u8> = (0..100_000).map(|i| ((i / 7) % 13) as u8).collect();
+        let mixed: Vec<u8> = (0..20_000u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).chain(repetitive.iter().copied()).collect();
+        for data in [vec![], vec![42], b"abcabcabcabcabc".to_vec(), repetitive.clone(), mixed] {
+            assert_eq!(zlib_decompress(&zlib_compress(&data)), data);
+        }
+        assert!(zlib_compress(&repetitive).len() < repetitive.len() / 20);
+    }
+
+    #[test]
+    fn adler32_of_known_string() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+}
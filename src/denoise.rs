@@ -0,0 +1,171 @@
+use crate::aov::{Aov, Frame};
+use crate::color::Color;
+use crate::image::Image;
+use crate::vec3::Vec3;
+
+// Edge-avoiding à-trous wavelet filter (Dammertz et al. 2010), the spatial part of SVGF. Each pass
+// blurs with a 5x5 B-spline kernel whose taps are spread twice as far apart as in the pass
+// before, so a few passes cover a wide area cheaply. Taps are weighted down where the first-hit
+// normal, albedo or color differs from the center pixel, which keeps edges and texture sharp.
+//
+// The filter works on the unclamped HDR radiance divided by albedo, so surface texture isn't
+// blurred along with the noise, and multiplies the albedo back in at the end.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Denoiser {
+    pub passes: u8,          // Filter radius is 2^passes pixels
+    pub color_sigma: f64,    // Color difference (after compressing highlights) that cuts a tap to 1/e of its weight
+    pub normal_power: f64,   // Exponent on the cosine between normals; higher keeps creases sharper
+    pub albedo_sigma: f64,   // Same as `color_sigma`, for the albedo
+}
+
+impl Default for Denoiser {
+    fn default() -> Denoiser {
+        Denoiser { passes: 5, color_sigma: 0.6, normal_power: 64., albedo_sigma: 0.1 }
+    }
+}
+
+const KERNEL: [f64; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+
+impl Denoiser {
+    // Panics unless the frame was rendered with the albedo and normal AOVs
+    pub fn denoise_frame(&self, frame: &Frame) -> Image {
+        let albedo = frame.aov(Aov::Albedo).expect("denoising needs the albedo AOV");
+        let normal = frame.aov(Aov::Normal).expect("denoising needs the normal AOV");
+        self.denoise(&frame.beauty, albedo, normal)
+    }
+
+    pub fn denoise(&self, beauty: &Image, albedo: &Image, normal: &Image) -> Image {
+        assert_eq!((albedo.width, albedo.height), (beauty.width, beauty.height), "albedo size doesn't match the image");
+        assert_eq!((normal.width, normal.height), (beauty.width, beauty.height), "normal size doesn't match the image");
+        // Black albedo (the sky, absorbers) has nothing to divide out
+        let divisor: Vec<Color> = albedo.pixels.iter().map(|albedo| albedo.map(|a| if a > 1e-3 { a } else { 1. })).collect();
+        let mut irradiance = beauty.clone();
+        for (pixel, divisor) in irradiance.pixels.iter_mut().zip(&divisor) {
+            *pixel = *pixel / divisor;
+        }
+
+        for pass in 0..self.passes {
+            // Later passes compare colors that earlier ones already smoothed, so they can be stricter
+            let color_sigma = self.color_sigma / (1 << pass) as f64;
+            irradiance = self.filter_pass(&irradiance, albedo, normal, 1 << pass, color_sigma);
+        }
+
+        for (pixel, divisor) in irradiance.pixels.iter_mut().zip(&divisor) {
+            *pixel = *pixel * divisor;
+        }
+        irradiance
+    }
+
+    fn filter_pass(&self, input: &Image, albedo: &Image, normal: &Image, step: i32, color_sigma: f64) -> Image {
+        let (width, height) = (input.width as i32, input.height as i32);
+        let mut output = Image::new(input.width, input.height);
+        for y in 0..height {
+            for x in 0..width {
+                let center = (x as u16, y as u16);
+                let center_color = compress(input.get(center.0, center.1));
+                let center_albedo = albedo.get(center.0, center.1);
+                let center_normal = normal.get(center.0, center.1);
+
+                let mut sum = Color::black();
+                let mut weight_sum = 0.;
+                for (j, ky) in KERNEL.iter().enumerate() {
+                    for (i, kx) in KERNEL.iter().enumerate() {
+                        let (tap_x, tap_y) = (x + (i as i32 - 2) * step, y + (j as i32 - 2) * step);
+                        if !(0..width).contains(&tap_x) || !(0..height).contains(&tap_y) {
+                            continue;
+                        }
+                        let (tap_x, tap_y) = (tap_x as u16, tap_y as u16);
+                        let color = input.get(tap_x, tap_y);
+
+                        let color_distance = (compress(color) - center_color).length_squared();
+                        let albedo_distance = (albedo.get(tap_x, tap_y) - center_albedo).length_squared();
+                        let cos_normals = Vec3::dot(&normal.get(tap_x, tap_y), &center_normal).max(0.);
+                        let weight = kx * ky
+                            * (-color_distance / (color_sigma * color_sigma)).exp()
+                            * (-albedo_distance / (self.albedo_sigma * self.albedo_sigma)).exp()
+                            * cos_normals.powf(self.normal_power);
+
+                        sum += color * weight;
+                        weight_sum += weight;
+                    }
+                }
+                // Where even the center has no weight (the sky has no normal), keep the pixel as is
+                let filtered = if weight_sum > 1e-12 { sum / weight_sum } else { input.get(center.0, center.1) };
+                output.set(center.0, center.1, filtered);
+            }
+        }
+        output
+    }
+}
+
+// Squeezes HDR values into [0, 1) so that fireflies don't make every neighbor look like an edge
+fn compress(color: Color) -> Color {
+    color.map(|c| c.max(0.) / (1. + c.max(0.)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::aov::Aov;
+    use crate::camera::Camera;
+    use crate::color::Color;
+    use crate::denoise::Denoiser;
+    use crate::hittable::Hittable;
+    use crate::hittable_collection::HittableCollection;
+    use crate::image::Image;
+    use crate::materials::lambertian::Lambertian;
+    use crate::progress::{CancellationToken, NoProgress};
+    use crate::shapes::plane::Plane;
+    use crate::sphere::Sphere;
+    use crate::vec3::{Point3, Vec3};
+
+    fn mean_squared_error(image: &Image, reference: &Image) -> f64 {
+        let total: f64 = image.pixels.iter().zip(&reference.pixels).map(|(a, b)| (a - b).length_squared()).sum();
+        total / image.pixels.len() as f64
+    }
+
+    fn scene() -> HittableCollection {
+        let objects: Vec<Box<dyn Hittable>> = vec![
+            Box::new(Plane::new(
+                Point3::new(0., -0.5, 0.),
+                Vec3::new(0., 1., 0.),
+                Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.2))),
+            )),
+            Box::new(Sphere::new(Point3::new(0., 0., -1.), 0.5, Arc::new(Lambertian::new(Color::new(0.2, 0.3, 0.7))))),
+        ];
+        HittableCollection::from(objects)
+    }
+
+    #[test]
+    fn denoising_brings_a_noisy_render_closer_to_the_reference() {
+        let world = scene();
+        let noisy = Camera::new(48, 16. / 9., 1, 8).with_aovs(&[Aov::Albedo, Aov::Normal]);
+        let noisy = noisy.render_frame(&world, &mut NoProgress, &CancellationToken::new());
+        let reference = Camera::new(48, 16. / 9., 16, 8).render(&world, &mut NoProgress, &CancellationToken::new());
+
+        let denoised = Denoiser::default().denoise_frame(&noisy);
+        let before = mean_squared_error(&noisy.beauty, &reference);
+        let after = mean_squared_error(&denoised, &reference);
+        assert!(after < before / 3., "{before} -> {after}");
+    }
+
+    #[test]
+    fn flat_noise_free_areas_are_left_alone() {
+        let mut beauty = Image::new(8, 8);
+        let mut albedo = Image::new(8, 8);
+        let mut normal = Image::new(8, 8);
+        for y in 0..8 {
+            for x in 0..8 {
+                // Two differently colored halves meeting at a crease
+                let left = x < 4;
+                beauty.set(x, y, if left { Color::new(2., 1., 0.5) } else { Color::new(0.1, 0.1, 0.1) });
+                albedo.set(x, y, if left { Color::new(0.8, 0.4, 0.2) } else { Color::new(0.5, 0.5, 0.5) });
+                normal.set(x, y, if left { Vec3::new(0., 1., 0.) } else { Vec3::new(1., 0., 0.) });
+            }
+        }
+        let denoised = Denoiser::default().denoise(&beauty, &albedo, &normal);
+        for (a, b) in denoised.pixels.iter().zip(&beauty.pixels) {
+            assert!((a - b).length() < 1e-9);
+        }
+    }
+}
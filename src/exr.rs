@@ -0,0 +1,363 @@
+use std::io;
+use std::io::Write;
+use crate::deflate::zlib_compress;
+use crate::image::Image;
+
+// Storage of one EXR channel
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PixelType {
+    Half,  // 16-bit float; plenty for colors and half the size
+    Float, // 32-bit float, for data like depth and position that needs the precision
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    // zlib over blocks of 16 scanlines, after a byte reordering and delta step that helps with floats
+    Zip,
+}
+
+#[derive(Clone, Debug)]
+pub struct ExrChannel {
+    pub name: String,
+    pub pixel_type: PixelType,
+    pub values: Vec<f32>, // Row-major, top row first
+}
+
+// Single-part scanline OpenEXR image with any number of channels. Layers follow the usual naming
+// convention: the channels of a layer are called "<layer>.<channel>", while the main (beauty)
+// image uses the bare names R, G and B.
+#[derive(Clone, Debug)]
+pub struct ExrImage {
+    pub width: u16,
+    pub height: u16,
+    pub channels: Vec<ExrChannel>,
+}
+
+impl ExrImage {
+    pub fn new(width: u16, height: u16) -> ExrImage {
+        ExrImage { width, height, channels: Vec::new() }
+    }
+
+    pub fn add_channel(&mut self, name: impl Into<String>, pixel_type: PixelType, values: Vec<f32>) {
+        assert_eq!(values.len(), self.width as usize * self.height as usize, "channel size doesn't match the image");
+        self.channels.push(ExrChannel { name: name.into(), pixel_type, values });
+    }
+
+    // Adds the R, G and B channels of `image` under `layer`, or as the main image if `layer` is empty
+    pub fn add_layer(&mut self, layer: &str, image: &Image, pixel_type: PixelType) {
+        assert_eq!((image.width, image.height), (self.width, self.height), "layer size doesn't match the image");
+        let prefix = if layer.is_empty() { String::new() } else { format!("{layer}.") };
+        let channel = |component: fn(&crate::color::Color) -> f64| image.pixels.iter().map(|pixel| component(pixel) as f32).collect();
+        self.add_channel(format!("{prefix}R"), pixel_type, channel(|pixel| pixel.x));
+        self.add_channel(format!("{prefix}G"), pixel_type, channel(|pixel| pixel.y));
+        self.add_channel(format!("{prefix}B"), pixel_type, channel(|pixel| pixel.z));
+    }
+
+    pub fn write(&self, out: &mut impl Write, compression: Compression) -> io::Result<()> {
+        // Readers expect the channels in alphabetical order, both in the header and in the pixel data
+        let mut channels: Vec<&ExrChannel> = self.channels.iter().collect();
+        channels.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]); // Magic number
+        header.extend_from_slice(&2u32.to_le_bytes()); // Version 2, single-part scanline
+
+        let mut channel_list = Vec::new();
+        for channel in &channels {
+            channel_list.extend_from_slice(channel.name.as_bytes());
+            channel_list.push(0);
+            let pixel_type: i32 = match channel.pixel_type {
+                PixelType::Half => 1,
+                PixelType::Float => 2,
+            };
+            channel_list.extend_from_slice(&pixel_type.to_le_bytes());
+            channel_list.extend_from_slice(&[0, 0, 0, 0]); // pLinear and reserved
+            channel_list.extend_from_slice(&1i32.to_le_bytes()); // x sampling
+            channel_list.extend_from_slice(&1i32.to_le_bytes()); // y sampling
+        }
+        channel_list.push(0);
+
+        let compression_code: u8 = match compression {
+            Compression::None => 0,
+            Compression::Zip => 3,
+        };
+        let (max_x, max_y) = (self.width as i32 - 1, self.height as i32 - 1);
+        let window: Vec<u8> = [0, 0, max_x, max_y].iter().flat_map(|value| value.to_le_bytes()).collect();
+        write_attribute(&mut header, "channels", "chlist", &channel_list);
+        write_attribute(&mut header, "compression", "compression", &[compression_code]);
+        write_attribute(&mut header, "dataWindow", "box2i", &window);
+        write_attribute(&mut header, "displayWindow", "box2i", &window);
+        write_attribute(&mut header, "lineOrder", "lineOrder", &[0]); // Increasing y
+        write_attribute(&mut header, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+        write_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+        write_attribute(&mut header, "screenWindowWidth", "float", &1f32.to_le_bytes());
+        header.push(0);
+
+        let lines_per_block = match compression {
+            Compression::None => 1,
+            Compression::Zip => 16,
+        };
+        let blocks: Vec<Vec<u8>> = (0..self.height as usize)
+            .step_by(lines_per_block)
+            .map(|first_line| {
+                let lines = first_line..(first_line + lines_per_block).min(self.height as usize);
+                let raw = self.block_data(&channels, lines);
+                let data = match compression {
+                    Compression::None => raw,
+                    Compression::Zip => {
+                        let compressed = zlib_compress(&predict(&interleave(&raw)));
+                        // Readers take a block that isn't smaller than the raw data as stored uncompressed
+                        if compressed.len() < raw.len() { compressed } else { raw }
+                    }
+                };
+                let mut block = Vec::with_capacity(data.len() + 8);
+                block.extend_from_slice(&(first_line as i32).to_le_bytes());
+                block.extend_from_slice(&(data.len() as i32).to_le_bytes());
+                block.extend_from_slice(&data);
+                block
+            })
+            .collect();
+
+        // Offset table: where each block starts, counted from the beginning of the file
+        let mut offset = (header.len() + 8 * blocks.len()) as u64;
+        out.write_all(&header)?;
+        for block in &blocks {
+            out.write_all(&offset.to_le_bytes())?;
+            offset += block.len() as u64;
+        }
+        for block in &blocks {
+            out.write_all(block)?;
+        }
+        Ok(())
+    }
+
+    // Pixel data of some scanlines: for each line, every channel's values for the whole line in turn
+    fn block_data(&self, channels: &[&ExrChannel], lines: std::ops::Range<usize>) -> Vec<u8> {
+        let width = self.width as usize;
+        let mut data = Vec::new();
+        for line in lines {
+            for channel in channels {
+                for &value in &channel.values[line * width..(line + 1) * width] {
+                    match channel.pixel_type {
+                        PixelType::Half => data.extend_from_slice(&f32_to_half(value).to_le_bytes()),
+                        PixelType::Float => data.extend_from_slice(&value.to_le_bytes()),
+                    }
+                }
+            }
+        }
+        data
+    }
+}
+
+impl Image {
+    // Writes the linear pixels as the main RGB layer of an EXR file
+    pub fn write_exr(&self, out: &mut impl Write, pixel_type: PixelType, compression: Compression) -> io::Result<()> {
+        let mut exr = ExrImage::new(self.width, self.height);
+        exr.add_layer("", self, pixel_type);
+        exr.write(out, compression)
+    }
+}
+
+fn write_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+// First the bytes at even positions, then the ones at odd positions. The high and low bytes of
+// the values end up in separate runs, which compress much better.
+fn interleave(raw: &[u8]) -> Vec<u8> {
+    raw.iter().step_by(2).chain(raw.iter().skip(1).step_by(2)).copied().collect()
+}
+
+// Replaces each byte by its difference to the previous one (offset by 128)
+fn predict(data: &[u8]) -> Vec<u8> {
+    let mut predicted = data.to_vec();
+    for i in 1..data.len() {
+        predicted[i] = data[i].wrapping_sub(data[i - 1]).wrapping_add(128);
+    }
+    predicted
+}
+
+// Nearest IEEE 754 half precision value, rounding ties to even. Too large values become infinity.
+pub fn f32_to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinity stays infinity, and NaN stays NaN by keeping a mantissa bit set
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    let round = |value: u32, dropped_bits: u32| {
+        let kept = value >> dropped_bits;
+        let remainder = value & ((1 << dropped_bits) - 1);
+        let halfway = 1 << (dropped_bits - 1);
+        if remainder > halfway || (remainder == halfway && kept & 1 == 1) { kept + 1 } else { kept }
+    };
+    if exponent <= 0 {
+        // Subnormal half; anything below half of the smallest one rounds to zero
+        if exponent < -10 {
+            return sign;
+        }
+        return sign | round(mantissa | 0x80_0000, (14 - exponent) as u32) as u16;
+    }
+    // A carry out of the mantissa correctly bumps the exponent, up to infinity
+    sign | round((exponent as u32) << 23 | mantissa, 13) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::deflate::tests::zlib_decompress;
+    use crate::exr::{f32_to_half, Compression, ExrImage, PixelType};
+    use crate::image::Image;
+
+    #[test]
+    fn half_conversion() {
+        assert_eq!(f32_to_half(0.), 0);
+        assert_eq!(f32_to_half(-0.), 0x8000);
+        assert_eq!(f32_to_half(1.), 0x3c00);
+        assert_eq!(f32_to_half(-2.), 0xc000);
+        assert_eq!(f32_to_half(0.1), 0x2e66);
+        assert_eq!(f32_to_half(65504.), 0x7bff);
+        assert_eq!(f32_to_half(65520.), 0x7c00); // Rounds up past the largest half
+        assert_eq!(f32_to_half(1e6), 0x7c00);
+        assert_eq!(f32_to_half(f32::INFINITY), 0x7c00);
+        assert!(f32_to_half(f32::NAN) & 0x3ff != 0);
+        assert_eq!(f32_to_half(5.960_464_5e-8), 0x0001); // Smallest subnormal
+        assert_eq!(f32_to_half(2e-8), 0);
+        assert_eq!(f32_to_half(1. + 1. / 2048.), 0x3c00); // Tie rounds to even
+        assert_eq!(f32_to_half(1. + 3. / 2048.), 0x3c02);
+    }
+
+    type Attributes = Vec<(String, Vec<u8>)>;
+    type Blocks = Vec<(i32, Vec<u8>)>;
+
+    // Header attributes by name, and the pixel data of every block after decompression
+    fn parse(file: &[u8], bytes_per_line: usize) -> (Attributes, Blocks) {
+        assert_eq!(&file[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+        let mut position = 8;
+        let read_string = |position: &mut usize| {
+            let end = file[*position..].iter().position(|&byte| byte == 0).unwrap() + *position;
+            let string = String::from_utf8(file[*position..end].to_vec()).unwrap();
+            *position = end + 1;
+            string
+        };
+        let read_i32 = |position: usize| i32::from_le_bytes(file[position..position + 4].try_into().unwrap());
+
+        let mut attributes = Vec::new();
+        loop {
+            let name = read_string(&mut position);
+            if name.is_empty() {
+                break;
+            }
+            let _kind = read_string(&mut position);
+            let size = read_i32(position) as usize;
+            attributes.push((name, file[position + 4..position + 4 + size].to_vec()));
+            position += 4 + size;
+        }
+
+        let compression = attributes.iter().find(|(name, _)| name == "compression").unwrap().1[0];
+        let window = &attributes.iter().find(|(name, _)| name == "dataWindow").unwrap().1;
+        let height = i32::from_le_bytes(window[12..16].try_into().unwrap()) + 1;
+        let lines_per_block = if compression == 3 { 16 } else { 1 };
+        let block_count = (height + lines_per_block - 1) / lines_per_block;
+
+        let blocks = (0..block_count as usize)
+            .map(|i| {
+                let offset = u64::from_le_bytes(file[position + 8 * i..position + 8 * i + 8].try_into().unwrap()) as usize;
+                let (y, size) = (read_i32(offset), read_i32(offset + 4) as usize);
+                let data = &file[offset + 8..offset + 8 + size];
+                let lines = lines_per_block.min(height - y) as usize;
+                let data = if compression == 3 && size < lines * bytes_per_line {
+                    // Undo the delta step and the byte reordering
+                    let mut predicted = zlib_decompress(data);
+                    for j in 1..predicted.len() {
+                        predicted[j] = predicted[j].wrapping_add(predicted[j - 1]).wrapping_sub(128);
+                    }
+                    let half = predicted.len().div_ceil(2);
+                    (0..predicted.len()).map(|j| if j % 2 == 0 { predicted[j / 2] } else { predicted[half + j / 2] }).collect()
+                } else {
+                    data.to_vec()
+                };
+                (y, data)
+            })
+            .collect();
+        (attributes, blocks)
+    }
+
+    // Six half channels and one float channel, five pixels each
+    const BYTES_PER_LINE: usize = 5 * (6 * 2 + 4);
+
+    fn test_image() -> ExrImage {
+        let mut beauty = Image::new(5, 20);
+        for y in 0..20 {
+            for x in 0..5 {
+                beauty.set(x, y, Color::new(x as f64 * 0.25, y as f64, 100.5));
+            }
+        }
+        let mut exr = ExrImage::new(5, 20);
+        exr.add_layer("", &beauty, PixelType::Half);
+        exr.add_layer("albedo", &beauty, PixelType::Half);
+        exr.add_channel("depth.Z", PixelType::Float, (0..100).map(|i| i as f32 * 1.5).collect());
+        exr
+    }
+
+    #[test]
+    fn channels_are_listed_alphabetically() {
+        let mut file = Vec::new();
+        test_image().write(&mut file, Compression::None).unwrap();
+        let (attributes, _) = parse(&file, BYTES_PER_LINE);
+
+        let channel_list = &attributes.iter().find(|(name, _)| name == "channels").unwrap().1;
+        let names: Vec<String> = channel_list
+            .split(|&byte| byte == 0)
+            .filter(|name| name.first().is_some_and(|byte| byte.is_ascii_alphabetic()))
+            .map(|name| String::from_utf8(name.to_vec()).unwrap())
+            .collect();
+        assert_eq!(names, ["B", "G", "R", "albedo.B", "albedo.G", "albedo.R", "depth.Z"]);
+    }
+
+    #[test]
+    fn uncompressed_scanlines_hold_every_channel_in_turn() {
+        let mut file = Vec::new();
+        test_image().write(&mut file, Compression::None).unwrap();
+        let (_, blocks) = parse(&file, BYTES_PER_LINE);
+        assert_eq!(blocks.len(), 20);
+
+        let (y, line) = &blocks[3];
+        assert_eq!(*y, 3);
+        assert_eq!(line.len(), BYTES_PER_LINE);
+        let half_at = |i: usize| u16::from_le_bytes([line[2 * i], line[2 * i + 1]]);
+        assert_eq!(half_at(0), f32_to_half(100.5)); // B of the first pixel
+        assert_eq!(half_at(5), f32_to_half(3.)); // G
+        assert_eq!(half_at(11), f32_to_half(0.25)); // R of the second pixel
+        let depth = f32::from_le_bytes(line[60..64].try_into().unwrap());
+        assert_eq!(depth, 15. * 1.5);
+    }
+
+    #[test]
+    fn zip_blocks_decompress_to_the_uncompressed_data() {
+        let (mut plain, mut zipped) = (Vec::new(), Vec::new());
+        test_image().write(&mut plain, Compression::None).unwrap();
+        test_image().write(&mut zipped, Compression::Zip).unwrap();
+        let (_, plain_blocks) = parse(&plain, BYTES_PER_LINE);
+        let (_, zip_blocks) = parse(&zipped, BYTES_PER_LINE);
+
+        assert_eq!(zip_blocks.iter().map(|(y, _)| *y).collect::<Vec<_>>(), [0, 16]);
+        let plain_data: Vec<u8> = plain_blocks.into_iter().flat_map(|(_, data)| data).collect();
+        let zip_data: Vec<u8> = zip_blocks.into_iter().flat_map(|(_, data)| data).collect();
+        assert_eq!(plain_data, zip_data);
+        assert!(zipped.len() < plain.len());
+    }
+}
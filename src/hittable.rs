@@ -1,5 +1,7 @@
 use std::sync::Arc;
+use crate::aabb::Aabb;
 use crate::interval::Interval;
+use crate::local_frame::LocalFrame;
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::{Point3, Vec3};
@@ -7,11 +9,209 @@ use crate::vec3::{Point3, Vec3};
 #[derive(Clone)]
 pub struct Hit {
     pub location: Point3,
-    pub normal: Vec3,
+    pub normal: Vec3, // Geometric normal, always pointing out of the object
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
     pub material: Arc<dyn Material>,
+    // How the surface moves as u and v change; together with the normal this is the tangent frame
+    // that normal and bump maps are expressed in
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    // Normal used for shading, also pointing outwards; the geometric `normal` unless a normal or
+    // bump map perturbed it
+    pub shading_normal: Vec3,
+    // Whether the ray hit the outside of the surface
+    pub front_face: bool,
+    // Shading basis with the shading normal turned towards the incoming ray as +z and the tangent
+    // following dpdu, so BSDFs can work in local coordinates
+    pub shading_frame: LocalFrame,
 }
 
-pub trait Hittable {
+impl Hit {
+    // The tangents start out as an arbitrary frame around the normal; shapes pass the real
+    // derivatives of their (u, v) mapping to `with_tangents`, which normal and bump maps rely on
+    pub fn new(ray: &Ray, t: f64, normal: Vec3, u: f64, v: f64, material: Arc<dyn Material>) -> Hit {
+        let (dpdu, dpdv) = normal.orthonormal_basis();
+        let mut hit = Hit {
+            location: ray.at(t),
+            normal,
+            t,
+            u,
+            v,
+            material,
+            dpdu,
+            dpdv,
+            shading_normal: normal,
+            front_face: Vec3::dot(&normal, &ray.direction) < 0.,
+            shading_frame: LocalFrame::new(normal),
+        };
+        hit.update_shading_frame();
+        hit
+    }
+
+    pub fn with_tangents(mut self, dpdu: Vec3, dpdv: Vec3) -> Hit {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+        self.update_shading_frame();
+        self
+    }
+
+    pub fn set_shading_normal(&mut self, shading_normal: Vec3) {
+        self.shading_normal = shading_normal;
+        self.update_shading_frame();
+    }
+
+    // Turns the surface inside out, for when the hit belongs to a hole cut into another object
+    pub fn flip(&mut self) {
+        self.normal = -self.normal;
+        self.shading_normal = -self.shading_normal;
+        self.front_face = !self.front_face;
+        self.update_shading_frame();
+    }
+
+    // Geometric normal on the side the ray came from
+    pub fn oriented_normal(&self) -> Vec3 {
+        if self.front_face {
+            self.normal
+        } else {
+            -self.normal
+        }
+    }
+
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        self.shading_frame.to_local(v)
+    }
+
+    pub fn to_world(&self, v: Vec3) -> Vec3 {
+        self.shading_frame.to_world(v)
+    }
+
+    // Has to be called after changing the normals or tangents directly
+    pub fn update_shading_frame(&mut self) {
+        let normal = if self.front_face {
+            self.shading_normal
+        } else {
+            -self.shading_normal
+        };
+        self.shading_frame = LocalFrame::aligned(normal, self.dpdu);
+    }
+}
+
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit>;
+
+    // Box enclosing the whole object, or None if it is unbounded (like `Plane`)
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::material::Material;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::sdf;
+    use crate::shapes::capsule::Capsule;
+    use crate::shapes::cone::Cone;
+    use crate::shapes::cylinder::Cylinder;
+    use crate::shapes::disk::Disk;
+    use crate::shapes::heightfield::Heightfield;
+    use crate::shapes::metaballs::{Metaball, Metaballs};
+    use crate::shapes::plane::Plane;
+    use crate::shapes::sdf_hittable::SdfHittable;
+    use crate::shapes::torus::Torus;
+    use crate::sphere::Sphere;
+    use crate::vec3::{Point3, Vec3};
+
+    #[test]
+    fn shading_frame_faces_the_incoming_ray() {
+        let sphere = Sphere::new(Vec3::zero(), 1., Arc::new(Lambertian::new(Color::white())));
+        let anywhere = Interval::new(0.001, f64::INFINITY);
+
+        let outside = sphere.hit(&Ray::new(Vec3::new(0., 0., 5.), Vec3::new(0., 0., -1.)), anywhere).unwrap();
+        assert!(outside.front_face);
+        assert_eq!(outside.oriented_normal(), Vec3::new(0., 0., 1.));
+
+        let inside = sphere.hit(&Ray::new(Vec3::zero(), Vec3::new(0., 0., 1.)), anywhere).unwrap();
+        assert!(!inside.front_face);
+        assert_eq!(inside.normal, Vec3::new(0., 0., 1.));
+        assert_eq!(inside.oriented_normal(), Vec3::new(0., 0., -1.));
+
+        // Local +z is the camera-side normal, and the tangent follows dpdu
+        for hit in [outside, inside] {
+            assert!((hit.to_local(hit.oriented_normal()) - Vec3::new(0., 0., 1.)).length() < 1e-12);
+            assert!(Vec3::dot(&hit.shading_frame.tangent, &hit.dpdu) > 0.);
+            let v = Vec3::new(0.2, -0.7, 0.4);
+            assert!((hit.to_world(hit.to_local(v)) - v).length() < 1e-12);
+        }
+    }
+
+    // Compares where two nearby rays hit with what dpdu and dpdv predict from the change in (u, v)
+    fn assert_tangents_match_uvs(shape: &dyn Hittable, origin: Point3, target: Point3) {
+        let anywhere = Interval::new(0.001, f64::INFINITY);
+        let direction = target - origin;
+        let hit = shape.hit(&Ray::new(origin, direction), anywhere).unwrap();
+        let (a, b) = direction.normalize().orthonormal_basis();
+        for offset in [a, b] {
+            let nearby = shape.hit(&Ray::new(origin + 1e-5 * offset, direction), anywhere).unwrap();
+            // Differences across a seam wrap around
+            let wrap = |delta: f64| delta - delta.round();
+            let moved = nearby.location - hit.location;
+            let predicted = wrap(nearby.u - hit.u) * hit.dpdu + wrap(nearby.v - hit.v) * hit.dpdv;
+            let error = (moved - predicted).length();
+            assert!(error < 1e-3 * moved.length(), "towards {target:?}: moved {moved:?}, predicted {predicted:?}");
+        }
+    }
+
+    #[test]
+    fn tangents_match_how_the_uvs_move() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::white()));
+        let up = Vec3::new(0., 1., 0.);
+
+        let cylinder = Cylinder::new(Vec3::zero(), up, 1., 2., true, material.clone());
+        assert_tangents_match_uvs(&cylinder, Point3::new(5., 1., 0.3), Point3::new(0., 1., 0.3));
+        assert_tangents_match_uvs(&cylinder, Point3::new(0.3, 5., 0.2), Point3::new(0.3, 0., 0.2));
+
+        let cone = Cone::new(Vec3::zero(), up, 1., 1., true, material.clone());
+        assert_tangents_match_uvs(&cone, Point3::new(5., 0.4, 0.2), Point3::new(0., 0.4, 0.2));
+        assert_tangents_match_uvs(&cone, Point3::new(0.3, -5., 0.2), Point3::new(0.3, 0., 0.2));
+
+        let torus = Torus::new(Vec3::zero(), up, 2., 0.5, material.clone());
+        assert_tangents_match_uvs(&torus, Point3::new(10., 0.2, 0.3), Point3::new(0., 0.2, 0.3));
+        assert_tangents_match_uvs(&torus, Point3::new(1.8, 5., 0.4), Point3::new(1.8, 0., 0.4));
+
+        let capsule = Capsule::new(Vec3::zero(), Vec3::new(0., 2., 0.), 0.5, material.clone());
+        assert_tangents_match_uvs(&capsule, Point3::new(5., 1., 0.1), Point3::new(0., 1., 0.1));
+        assert_tangents_match_uvs(&capsule, Point3::new(0.1, 5., 0.2), Point3::new(0.1, 0., 0.2));
+
+        let disk = Disk::new(Vec3::zero(), up, 1., material.clone());
+        assert_tangents_match_uvs(&disk, Point3::new(0.3, 5., 0.2), Point3::new(0.3, 0., 0.2));
+
+        let plane = Plane::new(Vec3::zero(), Vec3::new(0.2, 1., 0.1), material.clone());
+        assert_tangents_match_uvs(&plane, Point3::new(0.3, 5., 0.2), Point3::new(0.3, 0., 0.2));
+
+        let sphere = Sphere::new(Vec3::zero(), 1., material.clone());
+        assert_tangents_match_uvs(&sphere, Point3::new(0.3, 5., 0.2), Point3::new(0.3, 0., 0.2));
+
+        let heights = vec![0., 1., 0., 0., 1., 0., 0., 1., 0.];
+        let ridge = Heightfield::new(Vec3::zero(), Vec3::new(1., 1., 1.), 3, heights, material.clone());
+        assert_tangents_match_uvs(&ridge, Point3::new(0.3, 5., 0.6), Point3::new(0.3, 0., 0.6));
+
+        let balls = vec![
+            Metaball { center: Vec3::zero(), radius: 1., weight: 1. },
+            Metaball { center: Vec3::new(1.2, 0., 0.), radius: 1., weight: 1. },
+        ];
+        let metaballs = Metaballs::new(balls, 0.125, material.clone());
+        assert_tangents_match_uvs(&metaballs, Point3::new(0.3, 5., 0.4), Point3::new(0.3, 0., 0.4));
+
+        let mut sdf_torus = SdfHittable::new(Arc::new(sdf::torus(Vec3::zero(), 2., 0.5)), material);
+        sdf_torus.epsilon = 1e-10;
+        assert_tangents_match_uvs(&sdf_torus, Point3::new(2.1, 5., 0.3), Point3::new(2.1, 0., 0.3));
+    }
 }
@@ -1,7 +1,9 @@
+use crate::aabb::Aabb;
 use crate::hittable::{Hit, Hittable};
 use crate::interval::Interval;
 use crate::ray::Ray;
 
+#[derive(Default)]
 pub struct HittableCollection {
     pub objects: Vec<Box<dyn Hittable>>,
 }
@@ -18,31 +20,24 @@ impl HittableCollection {
     pub fn add(&mut self, object: Box<dyn Hittable>) {
         self.objects.push(object);
     }
-}
 
-impl From<Vec<Box<dyn Hittable>>> for HittableCollection {
-    fn from(value: Vec<Box<dyn Hittable>>) -> Self {
-        HittableCollection { objects: value }
-    }
-}
-
-impl Hittable for HittableCollection {
-    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
-        let mut closest_hit: Option<Hit> = None;
-        for object in &self.objects {
+    // Closest hit along with the index of the object it belongs to
+    pub fn hit_object(&self, ray: &Ray, t_interval: Interval) -> Option<(usize, Hit)> {
+        let mut closest_hit: Option<(usize, Hit)> = None;
+        for (index, object) in self.objects.iter().enumerate() {
             let max_distance_to_search = match closest_hit {
                 None => t_interval.max,
-                Some(ref hit) => hit.t,
+                Some((_, ref hit)) => hit.t,
             };
             let hit = object.hit(ray, Interval::new(t_interval.min, max_distance_to_search));
 
             match hit {
                 None => continue,
                 Some(hit) => match closest_hit {
-                    None => closest_hit = Some(hit),
-                    Some(ref previous_closest_hit) => {
+                    None => closest_hit = Some((index, hit)),
+                    Some((_, ref previous_closest_hit)) => {
                         if hit.t < previous_closest_hit.t {
-                            closest_hit = Some(hit);
+                            closest_hit = Some((index, hit));
                         }
                     }
                 },
@@ -52,3 +47,24 @@ impl Hittable for HittableCollection {
         closest_hit
     }
 }
+
+impl From<Vec<Box<dyn Hittable>>> for HittableCollection {
+    fn from(value: Vec<Box<dyn Hittable>>) -> Self {
+        HittableCollection { objects: value }
+    }
+}
+
+impl Hittable for HittableCollection {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        self.hit_object(ray, t_interval).map(|(_, hit)| hit)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // A single unbounded object makes the whole collection unbounded
+        self.objects
+            .iter()
+            .map(|object| object.bounding_box())
+            .reduce(|a, b| Some(a?.union(&b?)))
+            .flatten()
+    }
+}
@@ -0,0 +1,48 @@
+use std::io;
+use std::io::Write;
+use crate::color::{write_color, Color};
+use crate::tone_mapping::OutputTransform;
+
+// Linear pixel data in row-major order, top row first
+#[derive(Clone)]
+pub struct Image {
+    pub width: u16,
+    pub height: u16,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: u16, height: u16) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![Color::black(); width as usize * height as usize],
+        }
+    }
+
+    pub fn get(&self, x: u16, y: u16) -> Color {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: u16, y: u16, color: Color) {
+        let index = self.index(x, y);
+        self.pixels[index] = color;
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    // 8-bit PPM, with `transform` taking the linear pixels to display colors
+    pub fn write_ppm(&self, out: &mut impl Write, transform: &OutputTransform) -> io::Result<()> {
+        writeln!(out, "P3")?;
+        writeln!(out, "{} {}", self.width, self.height)?;
+        writeln!(out, "255")?;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                write_color(out, transform.quantize(self.get(x, y), x, y))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::{motion_fraction, Interval};
+use crate::mat4::Mat4;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// Object-to-world matrix together with its inverse, so neither has to be recomputed per ray
+#[derive(Copy, Clone, Debug)]
+pub struct Transform {
+    pub matrix: Mat4,
+    pub inverse: Mat4,
+}
+
+impl Transform {
+    pub fn new(matrix: Mat4) -> Option<Transform> {
+        Some(Transform { matrix, inverse: matrix.inverse()? })
+    }
+
+    pub fn identity() -> Transform {
+        Transform { matrix: Mat4::identity(), inverse: Mat4::identity() }
+    }
+
+    pub fn translation(offset: Vec3) -> Transform {
+        Transform { matrix: Mat4::translation(offset), inverse: Mat4::translation(-offset) }
+    }
+
+    // Applies `other` after `self`
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            matrix: other.matrix * self.matrix,
+            inverse: self.inverse * other.inverse,
+        }
+    }
+}
+
+// Places a shared object in the world through an affine transform.
+// Many instances can point at the same object without cloning it.
+#[derive(Clone)]
+pub struct Instance {
+    pub object: Arc<dyn Hittable>,
+    pub transform: Transform,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Instance {
+        Instance { object, transform }
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        hit_transformed(self.object.as_ref(), &self.transform, ray, t_interval)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.object.bounding_box()?.transformed(&self.transform.matrix))
+    }
+}
+
+// Instance that additionally slides by `motion` while `ray.time` goes over `motion_time`,
+// for motion blur on arbitrary objects
+#[derive(Clone)]
+pub struct MovingInstance {
+    pub object: Arc<dyn Hittable>,
+    pub transform: Transform,
+    pub motion: Vec3,
+    pub motion_time: Interval,
+}
+
+impl MovingInstance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform, motion: Vec3, motion_time: Interval) -> MovingInstance {
+        MovingInstance { object, transform, motion, motion_time }
+    }
+
+    pub fn transform_at(&self, time: f64) -> Transform {
+        let offset = motion_fraction(self.motion_time, time) * self.motion;
+        self.transform.then(&Transform::translation(offset))
+    }
+}
+
+impl Hittable for MovingInstance {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        hit_transformed(self.object.as_ref(), &self.transform_at(ray.time), ray, t_interval)
+    }
+
+    // The motion is a straight line, so the boxes at both ends enclose every position in between
+    fn bounding_box(&self) -> Option<Aabb> {
+        let start = self.object.bounding_box()?.transformed(&self.transform.matrix);
+        let end = start.transformed(&Mat4::translation(self.motion));
+        Some(start.union(&end))
+    }
+}
+
+fn hit_transformed(object: &dyn Hittable, transform: &Transform, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+    // The direction is left unnormalized so `t` means the same thing in both spaces
+    let object_ray = Ray::with_time(
+        transform.inverse.transform_point(ray.origin),
+        transform.inverse.transform_vector(ray.direction),
+        ray.time,
+    );
+
+    let mut hit = object.hit(&object_ray, t_interval)?;
+    hit.location = transform.matrix.transform_point(hit.location);
+    // Normals transform by the inverse transpose to stay perpendicular under non-uniform scaling
+    let normal_matrix = transform.inverse.transpose();
+    hit.normal = normal_matrix.transform_vector(hit.normal).normalize();
+    hit.shading_normal = normal_matrix.transform_vector(hit.shading_normal).normalize();
+    hit.dpdu = transform.matrix.transform_vector(hit.dpdu);
+    hit.dpdv = transform.matrix.transform_vector(hit.dpdv);
+    hit.update_shading_frame();
+    Some(hit)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::instance::{Instance, MovingInstance, Transform};
+    use crate::interval::Interval;
+    use crate::mat4::Mat4;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::sphere::Sphere;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn scaled_and_translated_sphere() {
+        let sphere = Sphere::new(Vec3::zero(), 1., Arc::new(Lambertian::new(Color::white())));
+        let matrix = Mat4::translation(Vec3::new(0., 0., -5.)) * Mat4::scaling(Vec3::new(1., 1., 2.));
+        let instance = Instance::new(Arc::new(sphere), Transform::new(matrix).unwrap());
+
+        let ray = Ray::new(Vec3::zero(), Vec3::new(0., 0., -1.));
+        let hit = instance.hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+
+        assert!((hit.t - 3.).abs() < 1e-9);
+        assert!((hit.location - Vec3::new(0., 0., -3.)).length() < 1e-9);
+        assert!((hit.normal - Vec3::new(0., 0., 1.)).length() < 1e-9);
+    }
+
+    #[test]
+    fn moving_instance_bounds_cover_the_whole_motion() {
+        let sphere = Sphere::new(Vec3::zero(), 1., Arc::new(Lambertian::new(Color::white())));
+        let moving = MovingInstance::new(
+            Arc::new(sphere),
+            Transform::translation(Vec3::new(0., 0., -5.)),
+            Vec3::new(0., 3., 0.),
+            Interval::new(0., 1.),
+        );
+
+        let ray = Ray::with_time(Vec3::new(0., 3., 0.), Vec3::new(0., 0., -1.), 1.);
+        let hit = moving.hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 4.).abs() < 1e-9);
+
+        let bounds = moving.bounding_box().unwrap();
+        assert_eq!((bounds.min.y, bounds.max.y), (-1., 4.));
+    }
+}
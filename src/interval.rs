@@ -4,17 +4,17 @@ pub struct Interval {
     pub max: f64,
 }
 
-const universe: Interval = Interval {
-    min: f64::NEG_INFINITY,
-    max: f64::INFINITY,
-};
+impl Interval {
+    pub const UNIVERSE: Interval = Interval {
+        min: f64::NEG_INFINITY,
+        max: f64::INFINITY,
+    };
 
-const empty: Interval = Interval {
-    min: f64::INFINITY,
-    max: f64::NEG_INFINITY,
-};
+    pub const EMPTY: Interval = Interval {
+        min: f64::INFINITY,
+        max: f64::NEG_INFINITY,
+    };
 
-impl Interval {
     pub fn new(min: f64, max: f64) -> Interval {
         Interval { min, max }
     }
@@ -35,3 +35,11 @@ impl Interval {
 pub fn clamp(num: f64, interval: Interval) -> f64 {
     num.clamp(interval.min, interval.max)
 }
+
+// How far along a motion over `motion_time` an object is at `time`, from 0 to 1
+pub fn motion_fraction(motion_time: Interval, time: f64) -> f64 {
+    if motion_time.size() <= 0. {
+        return 0.;
+    }
+    ((time - motion_time.min) / motion_time.size()).clamp(0., 1.)
+}
@@ -0,0 +1,175 @@
+// Indices of refraction that vary with wavelength, for the spectral mode. Wavelengths are in nm.
+
+// Complex index of refraction n + ik of a metal, tabulated at increasing wavelengths and linearly
+// interpolated in between. Outside the table the nearest end is used.
+#[derive(Copy, Clone, Debug)]
+pub struct MeasuredIor {
+    pub samples: &'static [(f64, f64, f64)], // (wavelength, n, k)
+}
+
+impl MeasuredIor {
+    pub fn at(&self, lambda: f64) -> (f64, f64) {
+        let samples = self.samples;
+        let upper = samples.partition_point(|&(sample_lambda, _, _)| sample_lambda < lambda);
+        if upper == 0 {
+            return (samples[0].1, samples[0].2);
+        }
+        if upper == samples.len() {
+            let (_, n, k) = samples[samples.len() - 1];
+            return (n, k);
+        }
+        let (lambda0, n0, k0) = samples[upper - 1];
+        let (lambda1, n1, k1) = samples[upper];
+        let fraction = (lambda - lambda0) / (lambda1 - lambda0);
+        (n0 + fraction * (n1 - n0), k0 + fraction * (k1 - k0))
+    }
+}
+
+// Measured metals, rounded and resampled every 50 nm (Johnson and Christy 1972; Rakić 1995 for aluminium)
+pub const GOLD: MeasuredIor = MeasuredIor {
+    samples: &[
+        (400., 1.658, 1.956),
+        (450., 1.500, 1.880),
+        (500., 0.970, 1.870),
+        (550., 0.430, 2.455),
+        (600., 0.250, 2.980),
+        (650., 0.170, 3.470),
+        (700., 0.160, 3.950),
+    ],
+};
+
+pub const COPPER: MeasuredIor = MeasuredIor {
+    samples: &[
+        (400., 1.180, 2.210),
+        (450., 1.170, 2.400),
+        (500., 1.130, 2.560),
+        (550., 1.020, 2.580),
+        (600., 0.270, 3.240),
+        (650., 0.210, 3.670),
+        (700., 0.210, 4.050),
+    ],
+};
+
+pub const SILVER: MeasuredIor = MeasuredIor {
+    samples: &[
+        (400., 0.050, 2.100),
+        (450., 0.040, 2.650),
+        (500., 0.050, 3.100),
+        (550., 0.060, 3.600),
+        (600., 0.060, 4.000),
+        (650., 0.050, 4.400),
+        (700., 0.040, 4.800),
+    ],
+};
+
+pub const ALUMINIUM: MeasuredIor = MeasuredIor {
+    samples: &[
+        (400., 0.490, 4.860),
+        (450., 0.620, 5.470),
+        (500., 0.770, 6.080),
+        (550., 0.960, 6.690),
+        (600., 1.200, 7.260),
+        (650., 1.470, 7.790),
+        (700., 1.830, 8.310),
+    ],
+};
+
+// Sellmeier equation n^2 = 1 + sum B_i l^2 / (l^2 - C_i), with l in micrometres as the
+// coefficients are usually published
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sellmeier {
+    pub b: [f64; 3],
+    pub c: [f64; 3],
+}
+
+impl Sellmeier {
+    pub fn ior(&self, lambda: f64) -> f64 {
+        let l2 = (lambda / 1000.).powi(2);
+        let n2 = 1. + (0..3).map(|i| self.b[i] * l2 / (l2 - self.c[i])).sum::<f64>();
+        n2.sqrt()
+    }
+}
+
+// Schott N-BK7, the common optical crown glass
+pub const BK7: Sellmeier = Sellmeier {
+    b: [1.03961212, 0.231792344, 1.01046945],
+    c: [0.00600069867, 0.0200179144, 103.560653],
+};
+
+// Fused silica (Malitson 1965)
+pub const FUSED_SILICA: Sellmeier = Sellmeier {
+    b: [0.6961663, 0.4079426, 0.8974794],
+    c: [0.004679148, 0.013512063, 97.93400],
+};
+
+// Schott SF11, a dense flint glass with strong dispersion
+pub const DENSE_FLINT: Sellmeier = Sellmeier {
+    b: [1.73759695, 0.313747346, 1.89878101],
+    c: [0.013188707, 0.0623068142, 155.23629],
+};
+
+// Cauchy's equation n = A + B / l^2 with l in micrometres, a simpler fit that is good enough for
+// most glasses over the visible range
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cauchy {
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Cauchy {
+    pub fn ior(&self, lambda: f64) -> f64 {
+        self.a + self.b / (lambda / 1000.).powi(2)
+    }
+}
+
+// Wavelength of the helium d line, where glass catalogues quote "the" index of refraction
+pub const D_LINE: f64 = 587.6;
+
+// Index of refraction of a dielectric as a function of wavelength
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Dispersion {
+    Cauchy(Cauchy),
+    Sellmeier(Sellmeier),
+}
+
+impl Dispersion {
+    pub fn ior(&self, lambda: f64) -> f64 {
+        match self {
+            Dispersion::Cauchy(cauchy) => cauchy.ior(lambda),
+            Dispersion::Sellmeier(sellmeier) => sellmeier.ior(lambda),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ior::{Cauchy, Dispersion, BK7, DENSE_FLINT, FUSED_SILICA, GOLD};
+
+    #[test]
+    fn glasses_match_published_indices() {
+        // Indices at the helium d line, 587.6 nm
+        assert!((BK7.ior(587.6) - 1.5168).abs() < 1e-3);
+        assert!((FUSED_SILICA.ior(587.6) - 1.4585).abs() < 1e-3);
+        assert!((DENSE_FLINT.ior(587.6) - 1.7847).abs() < 1e-3);
+        // Normal dispersion: blue bends more than red
+        assert!(BK7.ior(450.) > BK7.ior(650.));
+    }
+
+    #[test]
+    fn cauchy_fit_of_bk7_is_close_to_sellmeier() {
+        let cauchy = Dispersion::Cauchy(Cauchy { a: 1.5046, b: 0.00420 });
+        let sellmeier = Dispersion::Sellmeier(BK7);
+        for lambda in [450., 550., 650.] {
+            assert!((cauchy.ior(lambda) - sellmeier.ior(lambda)).abs() < 2e-3);
+        }
+    }
+
+    #[test]
+    fn measured_ior_interpolates_and_clamps() {
+        assert_eq!(GOLD.at(450.), (1.5, 1.88));
+        let (n, k) = GOLD.at(475.);
+        assert!((n - 1.235).abs() < 1e-9 && (k - 1.875).abs() < 1e-9);
+        assert_eq!(GOLD.at(300.), GOLD.at(400.));
+        assert_eq!(GOLD.at(800.), GOLD.at(700.));
+    }
+}
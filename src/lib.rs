@@ -0,0 +1,73 @@
+pub mod aabb;
+pub mod aov;
+pub mod camera;
+pub mod color;
+pub mod csg;
+mod deflate;
+pub mod denoise;
+pub mod exr;
+pub mod hittable;
+pub mod hittable_collection;
+pub mod image;
+pub mod instance;
+pub mod ior;
+pub mod interval;
+pub mod local_frame;
+pub mod mat4;
+pub mod material;
+pub mod materials;
+pub mod polynomial;
+pub mod post;
+pub mod progress;
+pub mod progressive_output;
+pub mod ray;
+pub mod sdf;
+pub mod shapes;
+pub mod solid;
+pub mod spectrum;
+pub mod sphere;
+pub mod texture;
+pub mod tone_mapping;
+pub mod vec3;
+pub mod volumes;
+
+pub use crate::aabb::Aabb;
+pub use crate::aov::{Aov, Frame, MaterialIds};
+pub use crate::camera::Camera;
+pub use crate::color::Color;
+pub use crate::csg::{Csg, CsgOperation};
+pub use crate::denoise::Denoiser;
+pub use crate::exr::{Compression, ExrImage, PixelType};
+pub use crate::hittable::{Hit, Hittable};
+pub use crate::hittable_collection::HittableCollection;
+pub use crate::image::Image;
+pub use crate::instance::{Instance, MovingInstance, Transform};
+pub use crate::interval::Interval;
+pub use crate::local_frame::LocalFrame;
+pub use crate::mat4::Mat4;
+pub use crate::material::{Material, Scattering};
+pub use crate::post::PostEffects;
+pub use crate::progress::{CancellationToken, Progress, ProgressObserver};
+pub use crate::progressive_output::{FlushSchedule, OutputFormat, ProgressiveOutput};
+pub use crate::ray::Ray;
+pub use crate::sdf::Sdf;
+pub use crate::shapes::axis_aligned_box::AxisAlignedBox;
+pub use crate::shapes::capsule::Capsule;
+pub use crate::shapes::cone::Cone;
+pub use crate::shapes::cylinder::Cylinder;
+pub use crate::shapes::disk::Disk;
+pub use crate::shapes::heightfield::Heightfield;
+pub use crate::shapes::metaballs::{Metaball, Metaballs};
+pub use crate::shapes::plane::Plane;
+pub use crate::shapes::quad::Quad;
+pub use crate::shapes::sdf_hittable::SdfHittable;
+pub use crate::shapes::torus::Torus;
+pub use crate::solid::{Solid, Span};
+pub use crate::spectrum::{SampledSpectrum, SampledWavelengths};
+pub use crate::sphere::{MovingSphere, Sphere};
+pub use crate::texture::{Input, Texture};
+pub use crate::tone_mapping::{OutputTransform, ToneMapper};
+pub use crate::vec3::{Point3, Vec3};
+pub use crate::volumes::constant_medium::ConstantMedium;
+pub use crate::volumes::grid_medium::GridMedium;
+pub use crate::volumes::voxel_grid::VoxelGrid;
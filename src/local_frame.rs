@@ -0,0 +1,57 @@
+use crate::vec3::Vec3;
+
+// Orthonormal frame around a normal, for writing BSDFs with the normal along +z
+#[derive(Copy, Clone, Debug)]
+pub struct LocalFrame {
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    pub normal: Vec3,
+}
+
+impl LocalFrame {
+    pub fn new(normal: Vec3) -> LocalFrame {
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        LocalFrame { tangent, bitangent, normal }
+    }
+
+    // Frame whose tangent follows `direction` as closely as possible, so anisotropic effects and
+    // normal maps line up with the surface parameterization. Falls back to `new` if `direction`
+    // is (nearly) parallel to the normal or zero.
+    pub fn aligned(normal: Vec3, direction: Vec3) -> LocalFrame {
+        let tangent = direction - Vec3::dot(&direction, &normal) * normal;
+        if tangent.length_squared() < 1e-24 {
+            return LocalFrame::new(normal);
+        }
+        let tangent = tangent.normalize();
+        LocalFrame { tangent, bitangent: Vec3::cross(&normal, &tangent), normal }
+    }
+
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(Vec3::dot(&v, &self.tangent), Vec3::dot(&v, &self.bitangent), Vec3::dot(&v, &self.normal))
+    }
+
+    pub fn to_world(&self, v: Vec3) -> Vec3 {
+        v.x * self.tangent + v.y * self.bitangent + v.z * self.normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::local_frame::LocalFrame;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn aligned_frame_is_orthonormal_and_round_trips() {
+        let normal = Vec3::new(1., 2., 2.) / 3.;
+        let frame = LocalFrame::aligned(normal, Vec3::new(1., 0., 0.));
+        assert!(Vec3::dot(&frame.tangent, &frame.normal).abs() < 1e-12);
+        assert!((frame.tangent.length() - 1.).abs() < 1e-12);
+        assert!((Vec3::cross(&frame.tangent, &frame.bitangent) - frame.normal).length() < 1e-12);
+        // The tangent leans towards the requested direction
+        assert!(frame.tangent.x > 0.9);
+
+        let v = Vec3::new(0.3, -0.5, 0.8);
+        assert!((frame.to_world(frame.to_local(v)) - v).length() < 1e-12);
+        assert!((frame.to_local(normal) - Vec3::new(0., 0., 1.)).length() < 1e-12);
+    }
+}
@@ -1,22 +1,17 @@
+use std::io;
+use std::io::Write;
 use std::sync::Arc;
-use crate::camera::Camera;
-use crate::color::Color;
-use crate::hittable::Hittable;
-use crate::hittable_collection::HittableCollection;
-use crate::sphere::Sphere;
-use crate::vec3::Vec3;
-mod camera;
-mod color;
-mod hittable;
-mod hittable_collection;
-mod interval;
-mod ray;
-mod sphere;
-mod vec3;
-mod material;
-mod materials;
-use crate::materials::lambertian::Lambertian;
-use crate::materials::metal::Metal;
+use diy_raytracing::camera::Camera;
+use diy_raytracing::color::Color;
+use diy_raytracing::hittable::Hittable;
+use diy_raytracing::hittable_collection::HittableCollection;
+use diy_raytracing::materials::lambertian::Lambertian;
+use diy_raytracing::materials::metal::Metal;
+use diy_raytracing::progress::{CancellationToken, Progress};
+use diy_raytracing::shapes::plane::Plane;
+use diy_raytracing::sphere::Sphere;
+use diy_raytracing::tone_mapping::{OutputTransform, ToneMapper};
+use diy_raytracing::vec3::Vec3;
 
 fn main() {
     let aspect_ratio = 16. / 9.;
@@ -26,28 +21,43 @@ fn main() {
 
     // world
     let objects: Vec<Box<dyn Hittable>> = vec![
-        Box::new(Sphere {
-            center: Vec3::new(0.0, -100.5, -1.0),
-            radius: 100.,
-            material: Arc::new(Lambertian { albedo: Color::new(0.8, 0.8, 0.0) }),
-        }),
-        Box::new(Sphere {
-            center: Vec3::new(0.0, 0.0, -1.0),
-            radius: 0.5,
-            material: Arc::new(Lambertian { albedo: Color::new(0.1, 0.2, 0.5) }),
-        }),
-        Box::new(Sphere {
-            center: Vec3::new(-1.0, 0.0, -1.0),
-            radius: 0.5,
-            material: Arc::new(Metal { albedo: Color::new(0.8, 0.8, 0.8), fuzz: 0.3 }),
-        }),
-        Box::new(Sphere {
-            center: Vec3::new(1.0, 0.0, -1.0),
-            radius: 0.5,
-            material: Arc::new(Metal { albedo: Color::new(0.8, 0.8, 0.0), fuzz: 1.0 }),
-        }),
+        Box::new(Plane::new(
+            Vec3::new(0.0, -0.5, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.0))),
+        )),
+        Box::new(Sphere::new(
+            Vec3::new(0.0, 0.0, -1.0),
+            0.5,
+            Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5))),
+        )),
+        Box::new(Sphere::new(
+            Vec3::new(-1.0, 0.0, -1.0),
+            0.5,
+            Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.3)),
+        )),
+        Box::new(Sphere::new(
+            Vec3::new(1.0, 0.0, -1.0),
+            0.5,
+            Arc::new(Metal::new(Color::new(0.8, 0.8, 0.0), 1.0)),
+        )),
     ];
     let world = HittableCollection::from(objects);
 
-    camera.render(&world);
+    let mut report_progress = |progress: &Progress| {
+        let eta = progress.eta().map_or(0, |eta| eta.as_secs());
+        eprint!(
+            "\r{:5.1}% done, ETA {}s, {:.0} rays/s",
+            progress.percent_done(),
+            eta,
+            progress.rays_per_second()
+        );
+    };
+    let image = camera.render(&world, &mut report_progress, &CancellationToken::new());
+    eprintln!();
+
+    let output_transform = OutputTransform::new(0., ToneMapper::Clamp).with_dither(true);
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    image.write_ppm(&mut out, &output_transform).expect("failed to write image");
+    out.flush().expect("failed to write image");
 }
@@ -0,0 +1,201 @@
+use std::ops;
+use crate::vec3::{Point3, Vec3};
+
+// Row-major 4x4 matrix for affine transforms. Points are treated as column vectors with w = 1,
+// directions with w = 0.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub const fn new(m: [[f64; 4]; 4]) -> Mat4 {
+        Mat4 { m }
+    }
+
+    pub const fn identity() -> Mat4 {
+        Mat4 {
+            m: [
+                [1., 0., 0., 0.],
+                [0., 1., 0., 0.],
+                [0., 0., 1., 0.],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    pub const fn translation(offset: Vec3) -> Mat4 {
+        Mat4 {
+            m: [
+                [1., 0., 0., offset.x],
+                [0., 1., 0., offset.y],
+                [0., 0., 1., offset.z],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    pub const fn scaling(factors: Vec3) -> Mat4 {
+        Mat4 {
+            m: [
+                [factors.x, 0., 0., 0.],
+                [0., factors.y, 0., 0.],
+                [0., 0., factors.z, 0.],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    pub fn rotation_x(radians: f64) -> Mat4 {
+        let (sin, cos) = radians.sin_cos();
+        Mat4 {
+            m: [
+                [1., 0., 0., 0.],
+                [0., cos, -sin, 0.],
+                [0., sin, cos, 0.],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    pub fn rotation_y(radians: f64) -> Mat4 {
+        let (sin, cos) = radians.sin_cos();
+        Mat4 {
+            m: [
+                [cos, 0., sin, 0.],
+                [0., 1., 0., 0.],
+                [-sin, 0., cos, 0.],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    pub fn rotation_z(radians: f64) -> Mat4 {
+        let (sin, cos) = radians.sin_cos();
+        Mat4 {
+            m: [
+                [cos, -sin, 0., 0.],
+                [sin, cos, 0., 0.],
+                [0., 0., 1., 0.],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    // Rotation around an arbitrary axis (Rodrigues' formula)
+    pub fn rotation(axis: Vec3, radians: f64) -> Mat4 {
+        let Vec3 { x, y, z } = axis.normalize();
+        let (sin, cos) = radians.sin_cos();
+        let t = 1. - cos;
+        Mat4 {
+            m: [
+                [t * x * x + cos, t * x * y - sin * z, t * x * z + sin * y, 0.],
+                [t * x * y + sin * z, t * y * y + cos, t * y * z - sin * x, 0.],
+                [t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos, 0.],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut m = [[0.; 4]; 4];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = self.m[col][row];
+            }
+        }
+        Mat4 { m }
+    }
+
+    // Gauss-Jordan elimination with partial pivoting. None if the matrix is singular.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::identity().m;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1. / a[col][col];
+            for k in 0..4 {
+                a[col][k] *= scale;
+                inv[col][k] *= scale;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for k in 0..4 {
+                    a[row][k] -= factor * a[col][k];
+                    inv[row][k] -= factor * inv[col][k];
+                }
+            }
+        }
+        Some(Mat4 { m: inv })
+    }
+
+    pub fn transform_point(&self, point: Point3) -> Point3 {
+        let m = &self.m;
+        let Vec3 { x, y, z } = point;
+        let result = Vec3 {
+            x: m[0][0] * x + m[0][1] * y + m[0][2] * z + m[0][3],
+            y: m[1][0] * x + m[1][1] * y + m[1][2] * z + m[1][3],
+            z: m[2][0] * x + m[2][1] * y + m[2][2] * z + m[2][3],
+        };
+        let w = m[3][0] * x + m[3][1] * y + m[3][2] * z + m[3][3];
+        if w == 1. { result } else { result / w }
+    }
+
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        let m = &self.m;
+        let Vec3 { x, y, z } = vector;
+        Vec3 {
+            x: m[0][0] * x + m[0][1] * y + m[0][2] * z,
+            y: m[1][0] * x + m[1][1] * y + m[1][2] * z,
+            z: m[2][0] * x + m[2][1] * y + m[2][2] * z,
+        }
+    }
+}
+
+impl ops::Mul<Mat4> for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Mat4) -> Mat4 {
+        let mut m = [[0.; 4]; 4];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[row][k] * rhs.m[k][col]).sum();
+            }
+        }
+        Mat4 { m }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mat4::Mat4;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn inverse_undoes_transform() {
+        let transform = Mat4::translation(Vec3::new(1., 2., 3.))
+            * Mat4::rotation(Vec3::new(1., 1., 0.), 0.7)
+            * Mat4::scaling(Vec3::new(2., 0.5, 3.));
+        let inverse = transform.inverse().unwrap();
+
+        let point = Vec3::new(-4., 5., 0.25);
+        let round_trip = inverse.transform_point(transform.transform_point(point));
+        assert!((round_trip - point).length() < 1e-12);
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        assert_eq!(Mat4::scaling(Vec3::new(1., 0., 1.)).inverse(), None);
+    }
+}
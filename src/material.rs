@@ -1,12 +1,36 @@
 use crate::color::Color;
 use crate::hittable::Hit;
 use crate::ray::Ray;
+use crate::spectrum::{SampledSpectrum, SampledWavelengths};
 
-pub trait Material {
+pub trait Material: Send + Sync {
     fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering>;
+
+    // Light given off by the surface itself, independent of what scatters off it
+    fn emitted(&self, _ray: &Ray, _hit: &Hit) -> Color {
+        Color::black()
+    }
+
+    // Surface color for the albedo AOV. Denoisers use it as a guide, so it must not be random:
+    // the same hit always gives the same color.
+    fn albedo(&self, _ray: &Ray, _hit: &Hit) -> Color {
+        Color::black()
+    }
+
+    // `scatter` for the spectral mode, where the path carries `wavelengths` instead of RGB.
+    // Materials whose behaviour depends on the wavelength override this; the rest are upsampled
+    // from their RGB attenuation.
+    fn scatter_spectral(&self, ray: &Ray, hit: &Hit, wavelengths: &mut SampledWavelengths) -> Option<Scattering<SampledSpectrum>> {
+        let scattering = self.scatter(ray, hit)?;
+        Some(Scattering {
+            attenuation: SampledSpectrum::from_rgb(scattering.attenuation, wavelengths),
+            scattered: scattering.scattered,
+        })
+    }
 }
 
-pub struct Scattering {
-    pub(crate) attenuation: Color,
-    pub(crate) scattered: Ray,
+// `A` is `Color` when rendering RGB and `SampledSpectrum` in the spectral mode
+pub struct Scattering<A = Color> {
+    pub attenuation: A,
+    pub scattered: Ray,
 }
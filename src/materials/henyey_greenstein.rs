@@ -0,0 +1,85 @@
+use std::f64::consts::PI;
+use rand::{rng, Rng};
+use crate::color::Color;
+use crate::hittable::Hit;
+use crate::material::{Material, Scattering};
+use crate::ray::Ray;
+
+// Phase function of a medium that prefers scattering forwards (g > 0) or backwards (g < 0).
+// g = 0 is isotropic; clouds are strongly forward scattering at around g = 0.85.
+pub struct HenyeyGreenstein {
+    pub albedo: Color,
+    pub g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein { albedo, g: g.clamp(-0.999, 0.999) }
+    }
+
+    // Probability density over solid angle of scattering by an angle whose cosine is `cos_theta`
+    pub fn phase(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        let denominator = 1. + g * g - 2. * g * cos_theta;
+        (1. - g * g) / (4. * PI * denominator * denominator.sqrt())
+    }
+
+    // Cosine of the angle to the incoming direction, sampled proportionally to `phase`
+    pub fn sample_cos_theta(&self, xi: f64) -> f64 {
+        let g = self.g;
+        if g.abs() < 1e-3 {
+            return 1. - 2. * xi;
+        }
+        let fraction = (1. - g * g) / (1. - g + 2. * g * xi);
+        ((1. + g * g - fraction * fraction) / (2. * g)).clamp(-1., 1.)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering> {
+        let forward = ray.direction.normalize();
+        let (tangent, bitangent) = forward.orthonormal_basis();
+
+        let cos_theta = self.sample_cos_theta(rng().random());
+        let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+        let phi = 2. * PI * rng().random::<f64>();
+        let direction = sin_theta * phi.cos() * tangent + sin_theta * phi.sin() * bitangent + cos_theta * forward;
+
+        Some(Scattering {
+            scattered: Ray::with_time(hit.location, direction, ray.time),
+            attenuation: self.albedo,
+        })
+    }
+
+    fn albedo(&self, _ray: &Ray, _hit: &Hit) -> Color {
+        self.albedo
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::materials::henyey_greenstein::HenyeyGreenstein;
+
+    #[test]
+    fn mean_cosine_of_samples_is_g() {
+        let phase = HenyeyGreenstein::new(Color::white(), 0.6);
+        let count = 10000;
+        let mean = (0..count)
+            .map(|i| phase.sample_cos_theta((i as f64 + 0.5) / count as f64))
+            .sum::<f64>() / count as f64;
+        assert!((mean - 0.6).abs() < 1e-3);
+    }
+
+    #[test]
+    fn phase_function_integrates_to_one() {
+        let phase = HenyeyGreenstein::new(Color::white(), -0.3);
+        let steps = 10000;
+        // Integrate over the sphere as 2 pi * integral of phase(cos) d(cos)
+        let integral: f64 = (0..steps)
+            .map(|i| -1. + 2. * (i as f64 + 0.5) / steps as f64)
+            .map(|cos_theta| phase.phase(cos_theta) * 2. / steps as f64)
+            .sum::<f64>() * 2. * std::f64::consts::PI;
+        assert!((integral - 1.).abs() < 1e-4);
+    }
+}
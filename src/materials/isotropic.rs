@@ -0,0 +1,31 @@
+use rand::distr::StandardUniform;
+use rand::{rng, Rng};
+use crate::color::Color;
+use crate::hittable::Hit;
+use crate::material::{Material, Scattering};
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// Phase function of a medium that scatters equally in every direction
+pub struct Isotropic {
+    pub albedo: Color,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Isotropic {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering> {
+        Some(Scattering {
+            scattered: Ray::with_time(hit.location, rng().sample::<Vec3, _>(StandardUniform), ray.time),
+            attenuation: self.albedo,
+        })
+    }
+
+    fn albedo(&self, _ray: &Ray, _hit: &Hit) -> Color {
+        self.albedo
+    }
+}
@@ -10,27 +10,32 @@ pub struct Lambertian {
     pub albedo: Color,
 }
 
+impl Lambertian {
+    pub fn new(albedo: Color) -> Lambertian {
+        Lambertian { albedo }
+    }
+}
+
 impl Material for Lambertian {
     fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering> {
-        let front_face = Vec3::dot(&hit.normal, &ray.direction) < 0.;
-        let camera_side_normal = if front_face {
-            hit.normal
-        } else {
-            -hit.normal
-        };
-
-        let mut scatter_direction = camera_side_normal + rng().sample::<Vec3, _>(StandardUniform);
+        let shading_normal = hit.shading_frame.normal;
+        let mut scatter_direction = shading_normal + rng().sample::<Vec3, _>(StandardUniform);
 
         if scatter_direction.length() < 1e-8 {
-            scatter_direction = camera_side_normal;
+            scatter_direction = shading_normal;
+        }
+        // A perturbed normal can send light into the surface it actually leaves from
+        if Vec3::dot(&scatter_direction, &hit.oriented_normal()) <= 0. {
+            return None;
         }
 
         Some(Scattering {
-            scattered: Ray {
-                origin: hit.location,
-                direction: scatter_direction,
-            },
+            scattered: Ray::with_time(hit.location, scatter_direction, ray.time),
             attenuation: self.albedo,
         })
     }
+
+    fn albedo(&self, _ray: &Ray, _hit: &Hit) -> Color {
+        self.albedo
+    }
 }
\ No newline at end of file
@@ -0,0 +1,161 @@
+use std::ops::MulAssign;
+use std::sync::Arc;
+use rand::{rng, Rng};
+use crate::color::Color;
+use crate::hittable::Hit;
+use crate::material::{Material, Scattering};
+use crate::materials::microfacet::{fresnel_dielectric, refract};
+use crate::ray::Ray;
+use crate::spectrum::{SampledSpectrum, SampledWavelengths};
+use crate::vec3::Vec3;
+
+// Smooth dielectric coat (like the clearcoat on car paint) on top of any base material.
+// Light is followed through the coat as a random walk: it reflects off the top or refracts in,
+// scatters off the base, then leaves or is reflected back down by the underside of the coat.
+#[derive(Clone)]
+pub struct Layered {
+    pub base: Arc<dyn Material>,
+    pub coat_ior: f64,
+    pub coat_color: Color, // Fraction of light that survives one pass straight through the coat
+    pub max_internal_bounces: u8,
+}
+
+impl Layered {
+    pub fn new(base: Arc<dyn Material>, coat_ior: f64, coat_color: Color) -> Layered {
+        Layered { base, coat_ior, coat_color, max_internal_bounces: 16 }
+    }
+
+    // Beer-Lambert absorption of a pass through the coat at `cos_theta` to the normal.
+    // Slanted paths are longer, so the straight-through transmittance is raised to 1 / cos.
+    fn absorption(&self, cos_theta: f64) -> Color {
+        let exponent = 1. / cos_theta.abs().max(1e-4);
+        self.coat_color.map(|channel| channel.powf(exponent))
+    }
+}
+
+impl Layered {
+    // The random walk through the coat, shared by the RGB and spectral modes; `A` is the type of
+    // the attenuation and `absorption` gives it for one pass through the coat at some cosine
+    fn walk<A: Copy + MulAssign>(
+        &self,
+        ray: &Ray,
+        hit: &Hit,
+        white: A,
+        absorption: impl Fn(f64) -> A,
+        mut scatter_base: impl FnMut(&Ray) -> Option<Scattering<A>>,
+    ) -> Option<Scattering<A>> {
+        let camera_side_normal = hit.oriented_normal();
+        let mut rng = rng();
+
+        let wo = -ray.direction.normalize();
+        let cos_o = Vec3::dot(&wo, &camera_side_normal);
+        if rng.random::<f64>() < fresnel_dielectric(cos_o, self.coat_ior) {
+            return Some(Scattering {
+                scattered: Ray::with_time(hit.location, ray.direction.reflect(camera_side_normal), ray.time),
+                attenuation: white,
+            });
+        }
+
+        // Refraction can't fail going into the denser coat
+        let mut inside = refract(wo, camera_side_normal, self.coat_ior)?;
+        let mut attenuation = absorption(Vec3::dot(&inside, &camera_side_normal));
+        for _ in 0..self.max_internal_bounces {
+            let scattering = scatter_base(&Ray::with_time(hit.location, inside, ray.time))?;
+            attenuation *= scattering.attenuation;
+            let up = scattering.scattered.direction.normalize();
+            let cos_up = Vec3::dot(&up, &camera_side_normal);
+            if cos_up <= 0. {
+                // The base sent the light further down, out of reach of the coat
+                return None;
+            }
+            attenuation *= absorption(cos_up);
+
+            // At the underside of the coat; total internal reflection has a Fresnel term of 1
+            let eta = 1. / self.coat_ior;
+            if rng.random::<f64>() < fresnel_dielectric(cos_up, eta) {
+                inside = up.reflect(camera_side_normal);
+                attenuation *= absorption(cos_up);
+                continue;
+            }
+            let out = refract(-up, -camera_side_normal, eta)?;
+            return Some(Scattering {
+                scattered: Ray::with_time(hit.location, out, ray.time),
+                attenuation,
+            });
+        }
+        None
+    }
+}
+
+impl Material for Layered {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering> {
+        self.walk(ray, hit, Color::white(), |cos| self.absorption(cos), |inside| self.base.scatter(inside, hit))
+    }
+
+    fn scatter_spectral(&self, ray: &Ray, hit: &Hit, wavelengths: &mut SampledWavelengths) -> Option<Scattering<SampledSpectrum>> {
+        let coat_color = SampledSpectrum::from_rgb(self.coat_color, wavelengths);
+        let absorption = |cos_theta: f64| {
+            let exponent = 1. / cos_theta.abs().max(1e-4);
+            SampledSpectrum(coat_color.0.map(|value| value.powf(exponent)))
+        };
+        let white = SampledSpectrum::constant(1.);
+        self.walk(ray, hit, white, absorption, |inside| self.base.scatter_spectral(inside, hit, wavelengths))
+    }
+
+    // The base seen through the coat
+    fn albedo(&self, ray: &Ray, hit: &Hit) -> Color {
+        self.base.albedo(ray, hit) * self.coat_color
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &Hit) -> Color {
+        let cos_o = Vec3::dot(&-ray.direction.normalize(), &hit.normal).abs();
+        let transmitted = 1. - fresnel_dielectric(cos_o, self.coat_ior);
+        self.base.emitted(ray, hit) * self.absorption(cos_o) * transmitted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hit;
+    use crate::material::Material;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::layered::Layered;
+    use crate::ray::Ray;
+    use crate::vec3::Vec3;
+
+    fn mean_attenuation(material: Layered) -> Color {
+        let material = Arc::new(material);
+        let ray = Ray::new(Vec3::new(0., 0., 1.), Vec3::new(0., 0., -1.));
+        let hit = Hit::new(&ray, 1., Vec3::new(0., 0., 1.), 0., 0., material.clone());
+        let samples = 20000;
+        let total: Color = (0..samples)
+            .filter_map(|_| material.scatter(&ray, &hit))
+            .map(|scattering| scattering.attenuation)
+            .sum();
+        total / samples as f64
+    }
+
+    #[test]
+    fn coat_over_black_only_reflects_fresnel() {
+        let layered = Layered::new(Arc::new(Lambertian::new(Color::black())), 1.5, Color::white());
+        assert!((mean_attenuation(layered).x - 0.04).abs() < 0.01);
+    }
+
+    #[test]
+    fn clear_coat_over_white_conserves_energy() {
+        let layered = Layered::new(Arc::new(Lambertian::new(Color::white())), 1.5, Color::white());
+        let energy = mean_attenuation(layered).x;
+        assert!((0.95..=1.01).contains(&energy), "{energy}");
+    }
+
+    #[test]
+    fn tinted_coat_absorbs_on_the_way_in_and_out() {
+        let layered = Layered::new(Arc::new(Lambertian::new(Color::white())), 1.5, Color::new(1., 0.5, 0.5));
+        let energy = mean_attenuation(layered);
+        assert!(energy.x > 0.95);
+        // At least two passes through the coat for everything but the 4% reflected off the top
+        assert!(energy.y < 0.04 + 0.96 * 0.25);
+    }
+}
@@ -11,23 +11,23 @@ pub struct Metal {
     pub(crate) fuzz: f64,
 }
 
+impl Metal {
+    pub fn new(albedo: Color, fuzz: f64) -> Metal {
+        Metal { albedo, fuzz }
+    }
+}
+
 impl Material for Metal {
     fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering> {
-
-        let front_face = Vec3::dot(&hit.normal, &ray.direction) < 0.;
-        let camera_side_normal = if front_face {
-            hit.normal
-        } else {
-            -hit.normal
-        };
-        let mut reflected = ray.direction.reflect(camera_side_normal);
+        let shading_normal = hit.shading_frame.normal;
+        let mut reflected = ray.direction.reflect(shading_normal);
         reflected = reflected.normalize() + (self.fuzz * rand::rng().sample::<Vec3, _>(StandardUniform));
 
         if reflected.length() < 1e-8 {
-            reflected = camera_side_normal;
+            reflected = shading_normal;
         }
-        let scattered = Ray { origin: hit.location, direction: reflected };
-        if Vec3::dot(&scattered.direction, &hit.normal) > 0. {
+        let scattered = Ray::with_time(hit.location, reflected, ray.time);
+        if Vec3::dot(&scattered.direction, &hit.oriented_normal()) > 0. {
             Some(Scattering {
                 scattered,
                 attenuation: self.albedo,
@@ -36,4 +36,8 @@ impl Material for Metal {
             None
         }
     }
+
+    fn albedo(&self, _ray: &Ray, _hit: &Hit) -> Color {
+        self.albedo
+    }
 }
\ No newline at end of file
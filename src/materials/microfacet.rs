@@ -0,0 +1,166 @@
+use std::f64::consts::PI;
+use crate::color::Color;
+use crate::vec3::Vec3;
+
+// GGX / Trowbridge-Reitz distribution of microfacet normals, in a local frame where the
+// macroscopic surface normal is +z. `alpha` is the square of the artist-facing roughness.
+#[derive(Copy, Clone, Debug)]
+pub struct Ggx {
+    pub alpha: f64,
+}
+
+impl Ggx {
+    pub fn from_roughness(roughness: f64) -> Ggx {
+        // Perfectly smooth GGX is a delta distribution, which sampling can't represent in floating point
+        let roughness = roughness.clamp(1e-3, 1.);
+        Ggx { alpha: roughness * roughness }
+    }
+
+    // Density of microfacet normals `h` per unit projected area
+    pub fn distribution(&self, h: Vec3) -> f64 {
+        if h.z <= 0. {
+            return 0.;
+        }
+        let a2 = self.alpha * self.alpha;
+        let denominator = h.z * h.z * (a2 - 1.) + 1.;
+        a2 / (PI * denominator * denominator)
+    }
+
+    // Smith's auxiliary function for the height-correlated masking-shadowing term
+    pub fn lambda(&self, w: Vec3) -> f64 {
+        let cos2 = w.z * w.z;
+        if cos2 == 0. {
+            return f64::INFINITY;
+        }
+        let tan2 = (1. - cos2).max(0.) / cos2;
+        0.5 * (-1. + (1. + self.alpha * self.alpha * tan2).sqrt())
+    }
+
+    // Fraction of microfacets facing `w` that are visible from it
+    pub fn masking(&self, w: Vec3) -> f64 {
+        1. / (1. + self.lambda(w))
+    }
+
+    // Fraction visible from both directions
+    pub fn masking_shadowing(&self, wo: Vec3, wi: Vec3) -> f64 {
+        1. / (1. + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Samples a microfacet normal proportionally to how much of it `wo` (with wo.z > 0) sees
+    // (Heitz 2018, "Sampling the GGX Distribution of Visible Normals")
+    pub fn sample_visible_normal(&self, wo: Vec3, u1: f64, u2: f64) -> Vec3 {
+        // Stretch the view direction into the configuration where the distribution is a hemisphere
+        let vh = Vec3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z).normalize();
+        let length_squared = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if length_squared > 0. {
+            Vec3::new(-vh.y, vh.x, 0.) / length_squared.sqrt()
+        } else {
+            Vec3::new(1., 0., 0.)
+        };
+        let t2 = Vec3::cross(&vh, &t1);
+
+        // Uniform point on a disk, warped towards the visible half
+        let r = u1.sqrt();
+        let phi = 2. * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1. + vh.z);
+        let p2 = (1. - s) * (1. - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1. - p1 * p1 - p2 * p2).max(0.).sqrt() * vh;
+
+        Vec3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(0.)).normalize()
+    }
+}
+
+// Reflectance of a metal for unpolarized light, per channel, from its complex index of refraction
+// eta + i k relative to the outside medium
+pub fn fresnel_conductor(cos_theta: f64, eta: Color, k: Color) -> Color {
+    Color::new(
+        fresnel_conductor_channel(cos_theta, eta.x, k.x),
+        fresnel_conductor_channel(cos_theta, eta.y, k.y),
+        fresnel_conductor_channel(cos_theta, eta.z, k.z),
+    )
+}
+
+// `fresnel_conductor` for a single wavelength or channel
+pub fn fresnel_conductor_channel(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos = cos_theta.clamp(0., 1.);
+    let cos2 = cos * cos;
+    let sin2 = 1. - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4. * eta2 * k2).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.).sqrt();
+    let t2 = 2. * cos * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rp + rs)
+}
+
+// Reflectance of the boundary into a dielectric with relative index `eta` (inside over outside),
+// seen from the outside at `cos_theta_i`. 1 under total internal reflection.
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_theta_i.clamp(0., 1.);
+    let sin2_t = (1. - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1. {
+        return 1.;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (r_parallel * r_parallel + r_perpendicular * r_perpendicular)
+}
+
+// Direction `w` (pointing away from the surface, on the side of `normal`) bends into when passing
+// into a medium with relative index `eta`. None under total internal reflection.
+pub fn refract(w: Vec3, normal: Vec3, eta: f64) -> Option<Vec3> {
+    let cos_i = Vec3::dot(&w, &normal);
+    let sin2_t = (1. - cos_i * cos_i).max(0.) / (eta * eta);
+    if sin2_t >= 1. {
+        return None;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+    Some(-w / eta + (cos_i / eta - cos_t) * normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::materials::microfacet::{fresnel_conductor, fresnel_dielectric, Ggx};
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn conductor_fresnel_at_normal_incidence() {
+        let (eta, k) = (0.2, 3.9);
+        let expected = ((eta - 1.) * (eta - 1.) + k * k) / ((eta + 1.) * (eta + 1.) + k * k);
+        let reflectance = fresnel_conductor(1., Color::new(eta, eta, eta), Color::new(k, k, k));
+        assert!((reflectance.x - expected).abs() < 1e-12);
+        // Every conductor turns into a perfect mirror at grazing angles
+        assert!((fresnel_conductor(0., Color::new(eta, eta, eta), Color::new(k, k, k)).x - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dielectric_fresnel() {
+        assert!((fresnel_dielectric(1., 1.5) - 0.04).abs() < 1e-12);
+        // From inside glass, beyond the critical angle of ~41.8 degrees
+        assert_eq!(fresnel_dielectric(0.5, 1. / 1.5), 1.);
+    }
+
+    #[test]
+    fn visible_normals_face_the_viewer() {
+        let ggx = Ggx::from_roughness(0.8);
+        let wo = Vec3::new(0.9, 0., 0.2).normalize();
+        for i in 0..100 {
+            for j in 0..10 {
+                let h = ggx.sample_visible_normal(wo, (i as f64 + 0.5) / 100., j as f64 / 10.);
+                assert!(Vec3::dot(&h, &wo) >= 0.);
+                assert!((h.length() - 1.).abs() < 1e-9);
+            }
+        }
+    }
+}
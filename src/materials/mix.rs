@@ -0,0 +1,84 @@
+use std::sync::Arc;
+use rand::{rng, Rng};
+use crate::color::Color;
+use crate::hittable::Hit;
+use crate::material::{Material, Scattering};
+use crate::ray::Ray;
+use crate::spectrum::{SampledSpectrum, SampledWavelengths};
+use crate::texture::Input;
+
+// Blend of two materials, e.g. rust patches on metal. Each bounce uses `second` with probability
+// `weight` (usually a texture mask) and `first` otherwise, which averages out to a linear blend.
+#[derive(Clone)]
+pub struct MixMaterial {
+    pub first: Arc<dyn Material>,
+    pub second: Arc<dyn Material>,
+    pub weight: Input<f64>,
+}
+
+impl MixMaterial {
+    pub fn new(first: Arc<dyn Material>, second: Arc<dyn Material>, weight: impl Into<Input<f64>>) -> MixMaterial {
+        MixMaterial { first, second, weight: weight.into() }
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering> {
+        if rng().random::<f64>() < self.weight.evaluate(hit) {
+            self.second.scatter(ray, hit)
+        } else {
+            self.first.scatter(ray, hit)
+        }
+    }
+
+    fn scatter_spectral(&self, ray: &Ray, hit: &Hit, wavelengths: &mut SampledWavelengths) -> Option<Scattering<SampledSpectrum>> {
+        if rng().random::<f64>() < self.weight.evaluate(hit) {
+            self.second.scatter_spectral(ray, hit, wavelengths)
+        } else {
+            self.first.scatter_spectral(ray, hit, wavelengths)
+        }
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &Hit) -> Color {
+        let weight = self.weight.evaluate(hit).clamp(0., 1.);
+        (1. - weight) * self.first.emitted(ray, hit) + weight * self.second.emitted(ray, hit)
+    }
+
+    fn albedo(&self, ray: &Ray, hit: &Hit) -> Color {
+        let weight = self.weight.evaluate(hit).clamp(0., 1.);
+        (1. - weight) * self.first.albedo(ray, hit) + weight * self.second.albedo(ray, hit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hit;
+    use crate::material::Material;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::mix::MixMaterial;
+    use crate::texture::Input;
+    use crate::ray::Ray;
+    use crate::vec3::{Point3, Vec3};
+
+    #[test]
+    fn texture_mask_selects_material() {
+        // Left half of the UV square is red, right half blue
+        let mask = Input::Texture(Arc::new(|u: f64, _v: f64, _p: Point3| {
+            if u < 0.5 { Color::black() } else { Color::white() }
+        }));
+        let material = Arc::new(MixMaterial::new(
+            Arc::new(Lambertian::new(Color::red())),
+            Arc::new(Lambertian::new(Color::blue())),
+            mask,
+        ));
+        let ray = Ray::new(Vec3::new(0., 0., 1.), Vec3::new(0., 0., -1.));
+        let hit_at = |u: f64| Hit::new(&ray, 1., Vec3::new(0., 0., 1.), u, 0., material.clone());
+
+        for _ in 0..10 {
+            assert_eq!(material.scatter(&ray, &hit_at(0.25)).unwrap().attenuation, Color::red());
+            assert_eq!(material.scatter(&ray, &hit_at(0.75)).unwrap().attenuation, Color::blue());
+        }
+    }
+}
@@ -1,2 +1,11 @@
+pub mod henyey_greenstein;
+pub mod isotropic;
 pub mod lambertian;
+pub mod layered;
 pub mod metal;
+pub mod microfacet;
+pub mod mix;
+pub mod normal_map;
+pub mod principled;
+pub mod rough_conductor;
+pub mod rough_dielectric;
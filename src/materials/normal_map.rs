@@ -0,0 +1,199 @@
+use std::sync::Arc;
+use crate::color::Color;
+use crate::hittable::Hit;
+use crate::material::{Material, Scattering};
+use crate::ray::Ray;
+use crate::spectrum::{SampledSpectrum, SampledWavelengths};
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+
+// Ways to add surface detail by bending the shading normal without changing the geometry
+#[derive(Clone)]
+pub enum Perturbation {
+    // Tangent-space normal map; red, green and blue go from -1 to 1 along dpdu, dpdv and the normal
+    NormalMap(Arc<dyn Texture>),
+    // Grayscale height map whose first channel is scaled by `scale` into world units
+    BumpMap { height: Arc<dyn Texture>, scale: f64 },
+}
+
+impl Perturbation {
+    pub fn shading_normal(&self, hit: &Hit) -> Vec3 {
+        let normal = hit.shading_normal;
+        match self {
+            Perturbation::NormalMap(texture) => {
+                let (tangent, bitangent) = tangent_frame(hit);
+                let mapped = 2. * texture.value(hit.u, hit.v, hit.location) - Color::white();
+                let perturbed = mapped.x * tangent + mapped.y * bitangent + mapped.z * normal;
+                if perturbed.length_squared() < 1e-12 {
+                    normal
+                } else {
+                    perturbed.normalize()
+                }
+            }
+            Perturbation::BumpMap { height, scale } => {
+                // Forward differences in texture space give the slope of the height along u and v
+                let delta = 1e-3;
+                let height_at = |u: f64, v: f64, location| scale * height.value(u, v, location).x;
+                let center = height_at(hit.u, hit.v, hit.location);
+                let dhdu = (height_at(hit.u + delta, hit.v, hit.location + delta * hit.dpdu) - center) / delta;
+                let dhdv = (height_at(hit.u, hit.v + delta, hit.location + delta * hit.dpdv) - center) / delta;
+
+                // Differentiating the displaced surface p + h n, ignoring the (small) change of n itself
+                let dpdu = hit.dpdu + dhdu * normal;
+                let dpdv = hit.dpdv + dhdv * normal;
+                let perturbed = Vec3::cross(&dpdu, &dpdv);
+                if perturbed.length_squared() < 1e-24 {
+                    return normal;
+                }
+                // Mirrored UV mappings have dpdu x dpdv pointing inwards
+                let orientation = Vec3::dot(&Vec3::cross(&hit.dpdu, &hit.dpdv), &normal).signum();
+                orientation * perturbed.normalize()
+            }
+        }
+    }
+}
+
+// Orthonormal tangent and bitangent around the shading normal, aligned with dpdu and keeping the
+// handedness of dpdv. Falls back to an arbitrary frame where dpdu vanishes, like at a sphere's poles.
+fn tangent_frame(hit: &Hit) -> (Vec3, Vec3) {
+    let normal = hit.shading_normal;
+    let tangent = hit.dpdu - Vec3::dot(&normal, &hit.dpdu) * normal;
+    if tangent.length_squared() < 1e-24 {
+        return normal.orthonormal_basis();
+    }
+    let tangent = tangent.normalize();
+    let bitangent = Vec3::cross(&normal, &tangent);
+    if Vec3::dot(&bitangent, &hit.dpdv) < 0. {
+        (tangent, -bitangent)
+    } else {
+        (tangent, bitangent)
+    }
+}
+
+// Wraps any material so it shades with a normal or bump mapped normal
+#[derive(Clone)]
+pub struct NormalMapped {
+    pub material: Arc<dyn Material>,
+    pub perturbation: Perturbation,
+}
+
+impl NormalMapped {
+    pub fn new(material: Arc<dyn Material>, perturbation: Perturbation) -> NormalMapped {
+        NormalMapped { material, perturbation }
+    }
+
+    fn perturbed(&self, hit: &Hit) -> Hit {
+        let mut hit = hit.clone();
+        hit.set_shading_normal(self.perturbation.shading_normal(&hit));
+        hit
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering> {
+        self.material.scatter(ray, &self.perturbed(hit))
+    }
+
+    fn scatter_spectral(&self, ray: &Ray, hit: &Hit, wavelengths: &mut SampledWavelengths) -> Option<Scattering<SampledSpectrum>> {
+        self.material.scatter_spectral(ray, &self.perturbed(hit), wavelengths)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &Hit) -> Color {
+        self.material.emitted(ray, &self.perturbed(hit))
+    }
+
+    fn albedo(&self, ray: &Ray, hit: &Hit) -> Color {
+        self.material.albedo(ray, hit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::{Hit, Hittable};
+    use crate::interval::Interval;
+    use crate::material::Material;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::normal_map::{NormalMapped, Perturbation};
+    use crate::ray::Ray;
+    use crate::shapes::cylinder::Cylinder;
+    use crate::shapes::quad::Quad;
+    use crate::vec3::{Point3, Vec3};
+
+    fn quad_hit(material: Arc<dyn Material>) -> (Ray, Hit) {
+        let quad = Quad::new(Vec3::zero(), Vec3::new(2., 0., 0.), Vec3::new(0., 2., 0.), material);
+        let ray = Ray::new(Vec3::new(1., 1., 1.), Vec3::new(0., 0., -1.));
+        let hit = quad.hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        (ray, hit)
+    }
+
+    fn shading_normal(perturbation: Perturbation) -> Vec3 {
+        let (_, hit) = quad_hit(Arc::new(Lambertian::new(Color::white())));
+        perturbation.shading_normal(&hit)
+    }
+
+    #[test]
+    fn normal_map_is_expressed_in_the_tangent_frame() {
+        let flat = Perturbation::NormalMap(Arc::new(Color::new(0.5, 0.5, 1.)));
+        assert!((shading_normal(flat) - Vec3::new(0., 0., 1.)).length() < 1e-9);
+
+        let along_u = Perturbation::NormalMap(Arc::new(Color::new(1., 0.5, 0.5)));
+        assert!((shading_normal(along_u) - Vec3::new(1., 0., 0.)).length() < 1e-9);
+
+        let along_v = Perturbation::NormalMap(Arc::new(Color::new(0.5, 1., 0.5)));
+        assert!((shading_normal(along_v) - Vec3::new(0., 1., 0.)).length() < 1e-9);
+    }
+
+    #[test]
+    fn bump_map_tilts_away_from_rising_height() {
+        // Height rises by 0.5 over the quad's u edge of length 2, a slope of 1/4 in world space
+        let ramp = Arc::new(|u: f64, _v: f64, _p: Point3| Color::new(u, u, u));
+        let bumped = shading_normal(Perturbation::BumpMap { height: ramp, scale: 0.5 });
+        assert!((bumped - Vec3::new(-0.25, 0., 1.).normalize()).length() < 1e-6);
+    }
+
+    #[test]
+    fn bump_slopes_on_a_cylinder_follow_u() {
+        // u runs once around the unit cylinder, 2 pi long, so this height rises by 1/4 per unit of arc
+        let material = Arc::new(Lambertian::new(Color::white()));
+        let cylinder = Cylinder::new(Vec3::zero(), Vec3::new(0., 1., 0.), 1., 2., false, material);
+        let ramp = Arc::new(|u: f64, _v: f64, _p: Point3| Color::new(u, u, u));
+        let bump = Perturbation::BumpMap { height: ramp, scale: 2. * PI / 4. };
+
+        let anywhere = Interval::new(0.001, f64::INFINITY);
+        let rays = [
+            Ray::new(Vec3::new(5., 1., 0.3), Vec3::new(-1., 0., 0.)),
+            Ray::new(Vec3::new(-0.4, 1.5, 5.), Vec3::new(0., 0., -1.)),
+        ];
+        for ray in rays {
+            let hit = cylinder.hit(&ray, anywhere).unwrap();
+            // Which way u grows, found by stepping around the cylinder instead of trusting dpdu
+            let around = Vec3::cross(&Vec3::new(0., 1., 0.), &hit.normal);
+            let step = cylinder.hit(&Ray::new(hit.location + hit.normal + 1e-4 * around, -hit.normal), anywhere).unwrap();
+            let rising = if step.u > hit.u { around } else { -around };
+
+            let expected = (hit.normal - 0.25 * rising).normalize();
+            assert!((bump.shading_normal(&hit) - expected).length() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn scattered_rays_stay_above_the_geometric_surface() {
+        let steep = Perturbation::NormalMap(Arc::new(Color::new(0.95, 0.5, 0.6)));
+        let material = NormalMapped::new(Arc::new(Lambertian::new(Color::white())), steep);
+        let (ray, hit) = quad_hit(Arc::new(material.clone()));
+
+        let mut scattered = 0;
+        for _ in 0..1000 {
+            if let Some(scattering) = material.scatter(&ray, &hit) {
+                assert!(scattering.scattered.direction.z > 0.);
+                assert_eq!(scattering.scattered.origin, hit.location);
+                scattered += 1;
+            }
+        }
+        // Lobe is tilted, so some directions are lost below the surface but most survive
+        assert!((500..1000).contains(&scattered));
+    }
+}
@@ -0,0 +1,207 @@
+use rand::distr::StandardUniform;
+use rand::{rng, Rng};
+use crate::color::Color;
+use crate::hittable::Hit;
+use crate::material::{Material, Scattering};
+use crate::materials::microfacet::{fresnel_dielectric, refract, Ggx};
+use crate::ray::Ray;
+use crate::texture::Input;
+use crate::vec3::Vec3;
+
+// Disney-style "uber" material with artist-friendly knobs, all in [0, 1] unless noted.
+// Every bounce picks a single lobe with a probability equal to its share of the energy, so the
+// weights stay at or below 1 and the material never creates light except through `emission`.
+#[derive(Clone)]
+pub struct Principled {
+    pub base_color: Input<Color>,
+    pub metallic: Input<f64>,
+    pub roughness: Input<f64>,
+    pub specular: Input<f64>,     // Dielectric reflectivity; 0.5 is the common 4% at normal incidence
+    pub sheen: Input<f64>,        // Soft velvet-like rim on the diffuse lobe
+    pub sheen_tint: Input<Color>,
+    pub clearcoat: Input<f64>,    // Strength of a separate glossy layer on top, like varnish
+    pub clearcoat_roughness: Input<f64>,
+    pub transmission: Input<f64>, // How much of the dielectric base is glass rather than diffuse
+    pub ior: f64,                 // Index of refraction used by transmission
+    pub emission: Input<Color>,
+    pub emission_strength: f64,
+}
+
+impl Principled {
+    pub fn new(base_color: impl Into<Input<Color>>) -> Principled {
+        Principled {
+            base_color: base_color.into(),
+            metallic: 0.0.into(),
+            roughness: 0.5.into(),
+            specular: 0.5.into(),
+            sheen: 0.0.into(),
+            sheen_tint: Color::white().into(),
+            clearcoat: 0.0.into(),
+            clearcoat_roughness: 0.03.into(),
+            transmission: 0.0.into(),
+            ior: 1.5,
+            emission: Color::black().into(),
+            emission_strength: 1.,
+        }
+    }
+}
+
+fn schlick(f0: f64, cos_theta: f64) -> f64 {
+    f0 + (1. - f0) * (1. - cos_theta.clamp(0., 1.)).powi(5)
+}
+
+fn schlick_color(f0: Color, cos_theta: f64) -> Color {
+    f0.map(|f0| schlick(f0, cos_theta))
+}
+
+// Specular reflection of `wo` about the microfacet `h`, weighted by the visible-normal sampling
+// weight G2 / G1
+fn reflect(ggx: &Ggx, wo: Vec3, h: Vec3, tint: Color) -> Option<(Vec3, Color)> {
+    let wi = (-wo).reflect(h);
+    if wi.z <= 0. {
+        return None;
+    }
+    Some((wi, tint * (ggx.masking_shadowing(wo, wi) / ggx.masking(wo))))
+}
+
+impl Principled {
+    // Direction (in the local frame of the camera-side normal) and weight of one bounce
+    fn sample(&self, wo: Vec3, hit: &Hit) -> Option<(Vec3, Color)> {
+        let mut rng = rng();
+
+        let clearcoat = self.clearcoat.evaluate(hit).clamp(0., 1.);
+        if clearcoat > 0. {
+            let ggx = Ggx::from_roughness(self.clearcoat_roughness.evaluate(hit));
+            let h = ggx.sample_visible_normal(wo, rng.random(), rng.random());
+            if rng.random::<f64>() < clearcoat * schlick(0.04, Vec3::dot(&wo, &h)) {
+                return reflect(&ggx, wo, h, Color::white());
+            }
+        }
+
+        let base_color = self.base_color.evaluate(hit);
+        let ggx = Ggx::from_roughness(self.roughness.evaluate(hit));
+        let h = ggx.sample_visible_normal(wo, rng.random(), rng.random());
+        let cos_theta = Vec3::dot(&wo, &h);
+
+        if rng.random::<f64>() < self.metallic.evaluate(hit) {
+            return reflect(&ggx, wo, h, schlick_color(base_color, cos_theta));
+        }
+
+        if rng.random::<f64>() < self.transmission.evaluate(hit) {
+            let eta = if hit.front_face { self.ior } else { 1. / self.ior };
+            if rng.random::<f64>() < fresnel_dielectric(cos_theta, eta) {
+                return reflect(&ggx, wo, h, Color::white());
+            }
+            let wi = refract(wo, h, eta)?;
+            if wi.z >= 0. {
+                return None;
+            }
+            let shadowing = ggx.masking_shadowing(wo, -wi) / ggx.masking(wo);
+            return Some((wi, base_color * shadowing));
+        }
+
+        let f0 = 0.08 * self.specular.evaluate(hit).clamp(0., 1.);
+        if rng.random::<f64>() < schlick(f0, cos_theta) {
+            return reflect(&ggx, wo, h, Color::white());
+        }
+
+        // Cosine-weighted diffuse bounce, blending towards the sheen color at grazing angles
+        let mut wi = Vec3::new(0., 0., 1.) + rng.sample::<Vec3, _>(StandardUniform);
+        if wi.length() < 1e-8 {
+            wi = Vec3::new(0., 0., 1.);
+        }
+        let sheen = self.sheen.evaluate(hit).clamp(0., 1.) * (1. - wo.z.clamp(0., 1.)).powi(5);
+        let sheen_tint = self.sheen_tint.evaluate(hit);
+        Some((wi.normalize(), (1. - sheen) * base_color + sheen * sheen_tint))
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering> {
+        let wo = hit.to_local(-ray.direction.normalize());
+        let (wi, attenuation) = self.sample(wo, hit)?;
+        Some(Scattering {
+            scattered: Ray::with_time(hit.location, hit.to_world(wi), ray.time),
+            attenuation,
+        })
+    }
+
+    fn emitted(&self, _ray: &Ray, hit: &Hit) -> Color {
+        self.emission.evaluate(hit) * self.emission_strength
+    }
+
+    fn albedo(&self, _ray: &Ray, hit: &Hit) -> Color {
+        self.base_color.evaluate(hit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hit;
+    use crate::material::Material;
+    use crate::materials::principled::Principled;
+    use crate::ray::Ray;
+    use crate::vec3::Vec3;
+
+    // Average energy a bounce keeps for light arriving at `angle` from the normal. Inside a white
+    // furnace (uniform unit radiance everywhere) this is exactly the radiance the surface reflects.
+    fn furnace(material: Principled, angle: f64) -> f64 {
+        let material = Arc::new(material);
+        let ray = Ray::new(Vec3::zero(), Vec3::new(angle.sin(), 0., -angle.cos()));
+        let hit = Hit::new(&ray, 1., Vec3::new(0., 0., 1.), 0., 0., material.clone());
+        let samples = 20000;
+        let total: f64 = (0..samples)
+            .filter_map(|_| material.scatter(&ray, &hit))
+            .map(|scattering| scattering.attenuation.y)
+            .sum();
+        total / samples as f64
+    }
+
+    #[test]
+    fn white_furnace_conserves_energy() {
+        type Configure = fn(&mut Principled);
+        // Single-scattering microfacet lobes lose the light that would bounce between microfacets,
+        // which is most visible on rough metal, hence the looser lower bound there
+        let configurations: [(&str, f64, Configure); 5] = [
+            ("diffuse", 0.9, |_| {}),
+            ("rough metal", 0.75, |m| {
+                m.metallic = 1.0.into();
+                m.roughness = 0.6.into();
+            }),
+            ("smooth metal", 0.9, |m| {
+                m.metallic = 1.0.into();
+                m.roughness = 0.05.into();
+            }),
+            ("glass", 0.9, |m| {
+                m.transmission = 1.0.into();
+                m.roughness = 0.1.into();
+            }),
+            ("coated velvet", 0.9, |m| {
+                m.clearcoat = 1.0.into();
+                m.sheen = 1.0.into();
+                m.specular = 1.0.into();
+            }),
+        ];
+        for (name, minimum, configure) in configurations {
+            for angle in [0., 0.7, 1.3] {
+                let mut material = Principled::new(Color::white());
+                configure(&mut material);
+                let energy = furnace(material, angle);
+                assert!(energy <= 1.01, "{name} at {angle} created energy: {energy}");
+                assert!(energy >= minimum, "{name} at {angle} lost too much energy: {energy}");
+            }
+        }
+    }
+
+    #[test]
+    fn emission_is_scaled_by_strength() {
+        let mut material = Principled::new(Color::black());
+        material.emission = Color::new(1., 0.5, 0.).into();
+        material.emission_strength = 4.;
+        let ray = Ray::new(Vec3::new(0., 0., 1.), Vec3::new(0., 0., -1.));
+        let hit = Hit::new(&ray, 1., Vec3::new(0., 0., 1.), 0., 0., Arc::new(Principled::new(Color::black())));
+        assert_eq!(material.emitted(&ray, &hit), Color::new(4., 2., 0.));
+    }
+}
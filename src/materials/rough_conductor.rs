@@ -0,0 +1,132 @@
+use rand::{rng, Rng};
+use crate::color::Color;
+use crate::hittable::Hit;
+use crate::material::{Material, Scattering};
+use crate::ior::{MeasuredIor, ALUMINIUM, COPPER, GOLD, SILVER};
+use crate::materials::microfacet::{fresnel_conductor, fresnel_conductor_channel, Ggx};
+use crate::ray::Ray;
+use crate::spectrum::{SampledSpectrum, SampledWavelengths};
+use crate::vec3::Vec3;
+
+// Metal with a GGX microfacet surface. Reflections are importance sampled from the visible normals,
+// so the weight of each bounce is just Fresnel times the Smith shadowing of the outgoing direction.
+pub struct RoughConductor {
+    pub eta: Color, // Real part of the complex index of refraction, per channel
+    pub k: Color,   // Imaginary part (extinction coefficient), per channel
+    pub ggx: Ggx,
+    pub measured: Option<MeasuredIor>, // Full curve for the spectral mode; `eta` and `k` are used without one
+}
+
+impl RoughConductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> RoughConductor {
+        RoughConductor { eta, k, ggx: Ggx::from_roughness(roughness), measured: None }
+    }
+
+    pub fn with_measured(mut self, measured: MeasuredIor) -> RoughConductor {
+        self.measured = Some(measured);
+        self
+    }
+
+    pub fn gold(roughness: f64) -> RoughConductor {
+        RoughConductor::new(Color::new(0.143, 0.374, 1.442), Color::new(3.983, 2.385, 1.603), roughness).with_measured(GOLD)
+    }
+
+    pub fn copper(roughness: f64) -> RoughConductor {
+        RoughConductor::new(Color::new(0.200, 0.924, 1.102), Color::new(3.912, 2.452, 2.142), roughness).with_measured(COPPER)
+    }
+
+    pub fn silver(roughness: f64) -> RoughConductor {
+        RoughConductor::new(Color::new(0.155, 0.117, 0.138), Color::new(4.827, 3.122, 2.147), roughness).with_measured(SILVER)
+    }
+
+    pub fn aluminium(roughness: f64) -> RoughConductor {
+        RoughConductor::new(Color::new(1.657, 0.880, 0.521), Color::new(9.224, 6.270, 4.837), roughness)
+            .with_measured(ALUMINIUM)
+    }
+
+    // Reflected direction in the local frame, the cosine between the outgoing direction and the
+    // sampled microfacet normal (for Fresnel), and the shadowing weight
+    fn sample(&self, wo: Vec3) -> Option<(Vec3, f64, f64)> {
+        let h = self.ggx.sample_visible_normal(wo, rng().random(), rng().random());
+        let wi = (-wo).reflect(h);
+        if wi.z <= 0. {
+            // Reflected into the surface; the energy is lost to multiple scattering we don't model
+            return None;
+        }
+        let shadowing = self.ggx.masking_shadowing(wo, wi) / self.ggx.masking(wo);
+        Some((wi, Vec3::dot(&wo, &h), shadowing))
+    }
+}
+
+impl Material for RoughConductor {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering> {
+        let wo = hit.to_local(-ray.direction.normalize());
+        let (wi, cos_theta, shadowing) = self.sample(wo)?;
+        let fresnel = fresnel_conductor(cos_theta, self.eta, self.k);
+        Some(Scattering {
+            scattered: Ray::with_time(hit.location, hit.to_world(wi), ray.time),
+            attenuation: fresnel * shadowing,
+        })
+    }
+
+    fn scatter_spectral(&self, ray: &Ray, hit: &Hit, wavelengths: &mut SampledWavelengths) -> Option<Scattering<SampledSpectrum>> {
+        let Some(measured) = self.measured else {
+            let scattering = self.scatter(ray, hit)?;
+            return Some(Scattering {
+                attenuation: SampledSpectrum::from_rgb(scattering.attenuation, wavelengths),
+                scattered: scattering.scattered,
+            });
+        };
+        let wo = hit.to_local(-ray.direction.normalize());
+        let (wi, cos_theta, shadowing) = self.sample(wo)?;
+        let fresnel = SampledSpectrum::from_fn(wavelengths, |lambda| {
+            let (eta, k) = measured.at(lambda);
+            fresnel_conductor_channel(cos_theta, eta, k)
+        });
+        Some(Scattering {
+            scattered: Ray::with_time(hit.location, hit.to_world(wi), ray.time),
+            attenuation: fresnel * shadowing,
+        })
+    }
+
+    // Reflectance head on, the color a metal is usually described by
+    fn albedo(&self, _ray: &Ray, _hit: &Hit) -> Color {
+        fresnel_conductor(1., self.eta, self.k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::hittable::Hit;
+    use crate::materials::rough_conductor::RoughConductor;
+    use crate::material::Material;
+    use crate::ray::Ray;
+    use crate::spectrum::SampledWavelengths;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn smooth_conductor_is_a_mirror() {
+        let material = Arc::new(RoughConductor::silver(0.));
+        let ray = Ray::new(Vec3::new(-1., 1., 0.), Vec3::new(1., -1., 0.));
+        let hit = Hit::new(&ray, 1., Vec3::new(0., 1., 0.), 0., 0., material.clone());
+        let scattering = material.scatter(&ray, &hit).unwrap();
+
+        let direction = scattering.scattered.direction.normalize();
+        assert!((direction - Vec3::new(1., 1., 0.).normalize()).length() < 1e-3);
+        assert!(scattering.attenuation.x > 0.9);
+    }
+
+    #[test]
+    fn spectral_gold_reflects_red_more_than_blue() {
+        let material = Arc::new(RoughConductor::gold(0.));
+        let ray = Ray::new(Vec3::new(0., 1., 0.), Vec3::new(0., -1., 0.));
+        let hit = Hit::new(&ray, 1., Vec3::new(0., 1., 0.), 0., 0., material.clone());
+        let mut wavelengths = SampledWavelengths { lambda: [450., 500., 600., 650.], pdf: [1.; 4] };
+        let reflectance = material.scatter_spectral(&ray, &hit, &mut wavelengths).unwrap().attenuation.0;
+
+        assert!(reflectance[0] < 0.45);
+        assert!(reflectance[3] > 0.9);
+        assert!(reflectance.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -0,0 +1,145 @@
+use rand::{rng, Rng};
+use crate::color::Color;
+use crate::hittable::Hit;
+use crate::ior::{Dispersion, D_LINE};
+use crate::material::{Material, Scattering};
+use crate::materials::microfacet::{fresnel_dielectric, refract, Ggx};
+use crate::ray::Ray;
+use crate::spectrum::{SampledSpectrum, SampledWavelengths};
+use crate::vec3::Vec3;
+
+// Glass with a GGX microfacet surface, e.g. frosted glass. Each bounce picks a visible microfacet,
+// then reflects or refracts through it with the Fresnel probability.
+pub struct RoughDielectric {
+    pub ior: f64,
+    pub ggx: Ggx,
+    pub tint: Color, // Multiplies light passing through the surface
+    // Index of refraction per wavelength, for rainbow fringes in the spectral mode; `ior` is used without one
+    pub dispersion: Option<Dispersion>,
+}
+
+impl RoughDielectric {
+    pub fn new(ior: f64, roughness: f64) -> RoughDielectric {
+        RoughDielectric { ior, ggx: Ggx::from_roughness(roughness), tint: Color::white(), dispersion: None }
+    }
+
+    // Glass made of a dispersive material. RGB renders use its index at the d line.
+    pub fn dispersive(dispersion: Dispersion, roughness: f64) -> RoughDielectric {
+        RoughDielectric { dispersion: Some(dispersion), ..RoughDielectric::new(dispersion.ior(D_LINE), roughness) }
+    }
+
+    // Direction in the local frame, whether it went through the surface, and the shadowing weight
+    fn sample(&self, wo: Vec3, ior: f64, front_face: bool) -> Option<(Vec3, bool, f64)> {
+        // Ratio of the index on the far side of the surface over the one the ray comes from
+        let eta = if front_face { ior } else { 1. / ior };
+        let h = self.ggx.sample_visible_normal(wo, rng().random(), rng().random());
+        let fresnel = fresnel_dielectric(Vec3::dot(&wo, &h), eta);
+
+        let (wi, refracted) = if rng().random::<f64>() < fresnel {
+            let wi = (-wo).reflect(h);
+            if wi.z <= 0. {
+                return None;
+            }
+            (wi, false)
+        } else {
+            let wi = refract(wo, h, eta)?;
+            if wi.z >= 0. {
+                return None;
+            }
+            (wi, true)
+        };
+
+        let shadowing = self.ggx.masking_shadowing(wo, Vec3::new(wi.x, wi.y, wi.z.abs())) / self.ggx.masking(wo);
+        Some((wi, refracted, shadowing))
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, ray: &Ray, hit: &Hit) -> Option<Scattering> {
+        let wo = hit.to_local(-ray.direction.normalize());
+        let (wi, refracted, shadowing) = self.sample(wo, self.ior, hit.front_face)?;
+        let attenuation = if refracted { self.tint } else { Color::white() };
+        Some(Scattering {
+            scattered: Ray::with_time(hit.location, hit.to_world(wi), ray.time),
+            attenuation: attenuation * shadowing,
+        })
+    }
+
+    fn scatter_spectral(&self, ray: &Ray, hit: &Hit, wavelengths: &mut SampledWavelengths) -> Option<Scattering<SampledSpectrum>> {
+        // Every wavelength would bend by a different amount, so only the hero can carry on
+        let ior = match self.dispersion {
+            Some(dispersion) => {
+                wavelengths.terminate_secondary();
+                dispersion.ior(wavelengths.hero())
+            }
+            None => self.ior,
+        };
+        let wo = hit.to_local(-ray.direction.normalize());
+        let (wi, refracted, shadowing) = self.sample(wo, ior, hit.front_face)?;
+        let attenuation = if refracted {
+            SampledSpectrum::from_rgb(self.tint, wavelengths)
+        } else {
+            SampledSpectrum::constant(1.)
+        };
+        Some(Scattering {
+            scattered: Ray::with_time(hit.location, hit.to_world(wi), ray.time),
+            attenuation: attenuation * shadowing,
+        })
+    }
+
+    fn albedo(&self, _ray: &Ray, _hit: &Hit) -> Color {
+        self.tint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::hittable::Hit;
+    use crate::ior::{Dispersion, DENSE_FLINT};
+    use crate::material::Material;
+    use crate::materials::rough_dielectric::RoughDielectric;
+    use crate::ray::Ray;
+    use crate::spectrum::SampledWavelengths;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn smooth_glass_reflects_four_percent_head_on() {
+        let material = Arc::new(RoughDielectric::new(1.5, 0.));
+        let ray = Ray::new(Vec3::new(0., 0., 1.), Vec3::new(0., 0., -1.));
+        let hit = Hit::new(&ray, 1., Vec3::new(0., 0., 1.), 0., 0., material.clone());
+
+        let samples = 20000;
+        let reflected = (0..samples)
+            .filter_map(|_| material.scatter(&ray, &hit))
+            .filter(|scattering| scattering.scattered.direction.z > 0.)
+            .count();
+        assert!((reflected as f64 / samples as f64 - 0.04).abs() < 0.01);
+    }
+
+    #[test]
+    fn blue_bends_more_than_red() {
+        let material = Arc::new(RoughDielectric::dispersive(Dispersion::Sellmeier(DENSE_FLINT), 0.));
+        assert!((material.ior - 1.7847).abs() < 1e-3);
+
+        let ray = Ray::new(Vec3::new(-1., 1., 0.), Vec3::new(1., -1., 0.));
+        let hit = Hit::new(&ray, 1., Vec3::new(0., 1., 0.), 0., 0., material.clone());
+        // Sideways component of the refracted direction; it shrinks the more the light bends
+        let refracted_at = |lambda: f64| loop {
+            let mut wavelengths = SampledWavelengths { lambda: [lambda, 500., 550., 600.], pdf: [1.; 4] };
+            let scattering = material.scatter_spectral(&ray, &hit, &mut wavelengths).unwrap();
+            assert!(wavelengths.secondary_terminated());
+            let direction = scattering.scattered.direction.normalize();
+            if direction.y < 0. {
+                break direction.x;
+            }
+        };
+        let (blue, red) = (refracted_at(450.), refracted_at(650.));
+        assert!(blue < red);
+        // Snell's law at 45 degrees with the index at each wavelength
+        let expected = |lambda: f64| std::f64::consts::FRAC_1_SQRT_2 / DENSE_FLINT.ior(lambda);
+        // Roughness is clamped just above zero, so the microfacet normals wobble a little
+        assert!((blue - expected(450.)).abs() < 1e-4);
+        assert!((red - expected(650.)).abs() < 1e-4);
+    }
+}
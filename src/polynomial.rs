@@ -0,0 +1,230 @@
+// Real roots of low-degree polynomials, returned in ascending order.
+// Coefficients are given from the highest power down.
+
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < 1e-12 {
+        if b.abs() < 1e-12 {
+            return vec![];
+        }
+        return vec![-c / b];
+    }
+    let discriminant = b * b - 4. * a * c;
+    if discriminant < 0. {
+        return vec![];
+    }
+    // Avoids cancellation between -b and the square root
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    if q == 0. {
+        return vec![0., 0.];
+    }
+    let mut roots = vec![q / a, c / q];
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a.abs() < 1e-12 {
+        return solve_quadratic(b, c, d);
+    }
+    let (b, c, d) = (b / a, c / a, d / a);
+
+    // Depressed cubic t^3 + pt + q with x = t - b/3
+    let shift = b / 3.;
+    let p = c - b * b / 3.;
+    let q = 2. * b * b * b / 27. - b * c / 3. + d;
+    let discriminant = (q / 2.) * (q / 2.) + (p / 3.) * (p / 3.) * (p / 3.);
+
+    let mut roots = if discriminant > 0. {
+        let sqrt_discriminant = discriminant.sqrt();
+        vec![(-q / 2. + sqrt_discriminant).cbrt() + (-q / 2. - sqrt_discriminant).cbrt()]
+    } else if p == 0. {
+        vec![0.]
+    } else {
+        let r = (-p / 3.).sqrt();
+        let phi = (3. * q / (2. * p) * (-3. / p).sqrt()).clamp(-1., 1.).acos();
+        (0..3)
+            .map(|k| 2. * r * (phi / 3. - 2. * std::f64::consts::PI * k as f64 / 3.).cos())
+            .collect()
+    };
+    for root in roots.iter_mut() {
+        *root = polish(&[1., b, c, d], *root - shift);
+    }
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+// Ferrari's method through the resolvent cubic, with every root refined by Newton's method
+// since the closed form loses precision for nearly double roots (e.g. rays grazing a torus)
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a.abs() < 1e-12 {
+        return solve_cubic(b, c, d, e);
+    }
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+
+    // Depressed quartic y^4 + py^2 + qy + r with x = y - b/4
+    let shift = b / 4.;
+    let b2 = b * b;
+    let p = c - 3. * b2 / 8.;
+    let q = d - b * c / 2. + b2 * b / 8.;
+    let r = e - b * d / 4. + b2 * c / 16. - 3. * b2 * b2 / 256.;
+
+    let mut roots = Vec::with_capacity(4);
+    if q.abs() < 1e-12 {
+        // Biquadratic: solve for y^2
+        for z in solve_quadratic(1., p, r) {
+            if z >= 0. {
+                roots.push(z.sqrt());
+                roots.push(-z.sqrt());
+            }
+        }
+    } else {
+        // The largest root of the resolvent is positive whenever q != 0
+        let m = solve_cubic(1., p, p * p / 4. - r, -q * q / 8.)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if m <= 0. {
+            return vec![];
+        }
+        let s = (2. * m).sqrt();
+        roots.extend(solve_quadratic(1., s, p / 2. + m - q / (2. * s)));
+        roots.extend(solve_quadratic(1., -s, p / 2. + m + q / (2. * s)));
+    }
+
+    for root in roots.iter_mut() {
+        *root = polish(&[1., b, c, d, e], *root - shift);
+    }
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+// Real roots of a polynomial of any degree inside [low, high]. Roots of the derivative split the range
+// into monotonic pieces, each of which holds at most one root that bisection then finds reliably.
+pub fn solve_in_range(coefficients: &[f64], low: f64, high: f64) -> Vec<f64> {
+    let leading = coefficients.iter().position(|c| c.abs() > 1e-300).unwrap_or(coefficients.len());
+    let coefficients = &coefficients[leading..];
+    let degree = coefficients.len().saturating_sub(1);
+    if degree == 0 {
+        return vec![];
+    }
+    if degree <= 2 {
+        let (a, b, c) = match coefficients {
+            [b, c] => (0., *b, *c),
+            [a, b, c] => (*a, *b, *c),
+            _ => unreachable!(),
+        };
+        return solve_quadratic(a, b, c)
+            .into_iter()
+            .filter(|root| (low..=high).contains(root))
+            .collect();
+    }
+
+    let derivative: Vec<f64> = coefficients[..degree]
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c * (degree - i) as f64)
+        .collect();
+    let mut bounds = vec![low];
+    bounds.extend(solve_in_range(&derivative, low, high));
+    bounds.push(high);
+
+    let mut roots: Vec<f64> = Vec::new();
+    for pair in bounds.windows(2) {
+        let (mut a, mut b) = (pair[0], pair[1]);
+        let (mut value_a, value_b) = (evaluate(coefficients, a).0, evaluate(coefficients, b).0);
+        let root = if value_a == 0. {
+            a
+        } else if value_b == 0. {
+            b
+        } else if value_a.signum() == value_b.signum() {
+            continue;
+        } else {
+            for _ in 0..64 {
+                let middle = 0.5 * (a + b);
+                let value_middle = evaluate(coefficients, middle).0;
+                if value_middle.signum() == value_a.signum() {
+                    a = middle;
+                    value_a = value_middle;
+                } else {
+                    b = middle;
+                }
+            }
+            0.5 * (a + b)
+        };
+        // Roots at the boundary between two pieces would otherwise be found twice
+        if roots.last().is_none_or(|&last| root - last > 1e-12) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+fn polish(coefficients: &[f64], mut x: f64) -> f64 {
+    for _ in 0..4 {
+        let (value, derivative) = evaluate(coefficients, x);
+        if derivative == 0. {
+            break;
+        }
+        let next = x - value / derivative;
+        if !next.is_finite() || evaluate(coefficients, next).0.abs() >= value.abs() {
+            break;
+        }
+        x = next;
+    }
+    x
+}
+
+// Horner's scheme for the value and the derivative at once
+fn evaluate(coefficients: &[f64], x: f64) -> (f64, f64) {
+    let mut value = 0.;
+    let mut derivative = 0.;
+    for coefficient in coefficients {
+        derivative = derivative * x + value;
+        value = value * x + coefficient;
+    }
+    (value, derivative)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::polynomial::{solve_cubic, solve_in_range, solve_quadratic, solve_quartic};
+
+    fn assert_roots(actual: Vec<f64>, expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+        }
+    }
+
+    #[test]
+    fn quadratic_roots() {
+        assert_roots(solve_quadratic(1., -3., 2.), &[1., 2.]);
+        assert_roots(solve_quadratic(1., 0., 1.), &[]);
+    }
+
+    #[test]
+    fn cubic_roots() {
+        // (x - 1)(x - 2)(x - 3)
+        assert_roots(solve_cubic(1., -6., 11., -6.), &[1., 2., 3.]);
+        // (x - 2)(x^2 + 1)
+        assert_roots(solve_cubic(1., -2., 1., -2.), &[2.]);
+    }
+
+    #[test]
+    fn quartic_roots() {
+        // (x + 1)(x - 2)(x - 3)(x - 5)
+        assert_roots(solve_quartic(1., -9., 21., 1., -30.), &[-1., 2., 3., 5.]);
+        // (x^2 - 4)(x^2 - 9)
+        assert_roots(solve_quartic(1., 0., -13., 0., 36.), &[-3., -2., 2., 3.]);
+        // (x^2 + 1)(x^2 + 2) has no real roots
+        assert_roots(solve_quartic(1., 0., 3., 0., 2.), &[]);
+    }
+
+    #[test]
+    fn roots_in_range_of_higher_degree() {
+        // (x + 1)(x - 2)(x - 3)(x - 5)(x - 6) = (x^4 - 9x^3 + 21x^2 + x - 30)(x - 6)
+        let coefficients = [1., -15., 75., -125., -36., 180.];
+        assert_roots(solve_in_range(&coefficients, 0., 5.5), &[2., 3., 5.]);
+        // (x - 1)^6 touches zero without changing sign
+        assert_roots(solve_in_range(&[1., -6., 15., -20., 15., -6., 1.], 0., 2.), &[1.]);
+    }
+}
@@ -0,0 +1,290 @@
+use crate::color::Color;
+use crate::image::Image;
+
+// Lens and film effects applied to the linear HDR image before it is tone mapped and quantized.
+// Each one is off unless set, and none of them are random, so the same image always comes out
+// the same.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PostEffects {
+    pub bloom: Option<Bloom>,
+    pub glare: Option<Glare>,
+    pub chromatic_aberration: Option<ChromaticAberration>,
+    pub vignette: Option<Vignette>,
+}
+
+// Soft glow around everything brighter than `threshold`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bloom {
+    pub threshold: f64, // Only light above this spills over
+    pub strength: f64,  // Share of the light above the threshold that is spread out
+    pub radius: f64,    // Standard deviation of the Gaussian glow, in pixels
+}
+
+// Star-shaped streaks from bright points, like the diffraction spikes of an aperture with straight blades
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Glare {
+    pub threshold: f64,
+    pub strength: f64,
+    pub streaks: u8,     // Lines through each bright point; 2 gives a cross, 3 a six-pointed star
+    pub length: u16,     // How far each streak reaches, in pixels
+    pub falloff: f64,    // Brightness lost per pixel along a streak, in (0, 1]
+    pub rotation: f64,   // Angle of the first streak from horizontal, in radians
+}
+
+// Red and blue focus at slightly different magnifications, so colors fringe towards the corners
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChromaticAberration {
+    pub strength: f64, // How far red and blue move apart at the corners, in pixels
+}
+
+// Darkening towards the edges of the frame
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Vignette {
+    pub strength: f64, // How much light the corners lose, from 0 (none) to 1 (all)
+}
+
+impl PostEffects {
+    pub fn apply(&self, image: &Image) -> Image {
+        let mut result = image.clone();
+        // Glows come from the light of the original image, before other effects move it around
+        if let Some(bloom) = self.bloom {
+            add(&mut result, &bloom.glow(image));
+        }
+        if let Some(glare) = self.glare {
+            add(&mut result, &glare.streaks(image));
+        }
+        if let Some(chromatic_aberration) = self.chromatic_aberration {
+            result = chromatic_aberration.apply(&result);
+        }
+        if let Some(vignette) = self.vignette {
+            vignette.apply(&mut result);
+        }
+        result
+    }
+}
+
+impl Bloom {
+    fn glow(&self, image: &Image) -> Image {
+        let mut bright = bright_pass(image, self.threshold);
+        blur(&mut bright, self.radius);
+        for pixel in &mut bright.pixels {
+            *pixel *= self.strength;
+        }
+        bright
+    }
+}
+
+impl Glare {
+    fn streaks(&self, image: &Image) -> Image {
+        let bright = bright_pass(image, self.threshold);
+        let mut streaks = Image::new(image.width, image.height);
+        // Both ways along every streak; the weights are normalized so `strength` is the share of the
+        // bright light that ends up in streaks
+        let directions: Vec<(f64, f64)> = (0..self.streaks)
+            .flat_map(|i| {
+                let angle = self.rotation + i as f64 * std::f64::consts::PI / self.streaks as f64;
+                [(angle.cos(), angle.sin()), (-angle.cos(), -angle.sin())]
+            })
+            .collect();
+        let weights: Vec<f64> = (1..=self.length).map(|step| self.falloff.powi(step as i32)).collect();
+        let total_weight = weights.iter().sum::<f64>() * directions.len() as f64;
+        if total_weight == 0. {
+            return streaks;
+        }
+
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let mut sum = Color::black();
+                for (dx, dy) in &directions {
+                    for (step, weight) in weights.iter().enumerate() {
+                        let distance = (step + 1) as f64;
+                        let source_x = (x as f64 + dx * distance).round();
+                        let source_y = (y as f64 + dy * distance).round();
+                        if (0. ..image.width as f64).contains(&source_x) && (0. ..image.height as f64).contains(&source_y) {
+                            sum += bright.get(source_x as u16, source_y as u16) * *weight;
+                        }
+                    }
+                }
+                streaks.set(x, y, sum * (self.strength / total_weight));
+            }
+        }
+        streaks
+    }
+}
+
+impl ChromaticAberration {
+    fn apply(&self, image: &Image) -> Image {
+        let mut result = Image::new(image.width, image.height);
+        let (center_x, center_y) = (image.width as f64 / 2., image.height as f64 / 2.);
+        let corner = (center_x * center_x + center_y * center_y).sqrt();
+        // Red is magnified and blue shrunk by the same amount, which moves them `strength` apart at the corners
+        let scale = self.strength / 2. / corner;
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let (offset_x, offset_y) = (x as f64 + 0.5 - center_x, y as f64 + 0.5 - center_y);
+                let at = |magnification: f64| {
+                    sample_bilinear(image, center_x + offset_x * magnification - 0.5, center_y + offset_y * magnification - 0.5)
+                };
+                let red = at(1. - scale).x;
+                let green = image.get(x, y).y;
+                let blue = at(1. + scale).z;
+                result.set(x, y, Color::new(red, green, blue));
+            }
+        }
+        result
+    }
+}
+
+impl Vignette {
+    fn apply(&self, image: &mut Image) {
+        let (center_x, center_y) = (image.width as f64 / 2., image.height as f64 / 2.);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                // Squared distance from the center, 1 at the corners
+                let u = (x as f64 + 0.5 - center_x) / center_x;
+                let v = (y as f64 + 0.5 - center_y) / center_y;
+                let r2 = (u * u + v * v) / 2.;
+                let falloff = 1. - self.strength.clamp(0., 1.) * r2;
+                image.set(x, y, image.get(x, y) * falloff);
+            }
+        }
+    }
+}
+
+// Light above `threshold`, scaled down evenly across channels so hues are kept
+fn bright_pass(image: &Image, threshold: f64) -> Image {
+    let mut bright = image.clone();
+    for pixel in &mut bright.pixels {
+        let brightest = pixel.x.max(pixel.y).max(pixel.z);
+        let excess = (brightest - threshold).max(0.);
+        *pixel = if brightest > 0. { *pixel * (excess / brightest) } else { Color::black() };
+    }
+    bright
+}
+
+// Separable Gaussian; pixels past the edges are left out and the remaining weights renormalized
+fn blur(image: &mut Image, sigma: f64) {
+    if sigma <= 0. {
+        return;
+    }
+    let radius = (3. * sigma).ceil() as i32;
+    let kernel: Vec<f64> = (-radius..=radius).map(|i| (-(i * i) as f64 / (2. * sigma * sigma)).exp()).collect();
+    let (width, height) = (image.width as i32, image.height as i32);
+    let pass = |image: &Image, horizontal: bool| {
+        let mut result = Image::new(image.width, image.height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = Color::black();
+                let mut weight_sum = 0.;
+                for (i, weight) in (-radius..=radius).zip(&kernel) {
+                    let (source_x, source_y) = if horizontal { (x + i, y) } else { (x, y + i) };
+                    if (0..width).contains(&source_x) && (0..height).contains(&source_y) {
+                        sum += image.get(source_x as u16, source_y as u16) * *weight;
+                        weight_sum += weight;
+                    }
+                }
+                result.set(x as u16, y as u16, sum / weight_sum);
+            }
+        }
+        result
+    };
+    let horizontal = pass(image, true);
+    *image = pass(&horizontal, false);
+}
+
+// Pixel centers are at whole coordinates here; outside the image the nearest edge pixel is used
+fn sample_bilinear(image: &Image, x: f64, y: f64) -> Color {
+    let x = x.clamp(0., (image.width - 1) as f64);
+    let y = y.clamp(0., (image.height - 1) as f64);
+    let (x0, y0) = (x.floor() as u16, y.floor() as u16);
+    let (x1, y1) = ((x0 + 1).min(image.width - 1), (y0 + 1).min(image.height - 1));
+    let (fx, fy) = (x - x0 as f64, y - y0 as f64);
+    let top = image.get(x0, y0) * (1. - fx) + image.get(x1, y0) * fx;
+    let bottom = image.get(x0, y1) * (1. - fx) + image.get(x1, y1) * fx;
+    top * (1. - fy) + bottom * fy
+}
+
+fn add(image: &mut Image, other: &Image) {
+    for (pixel, other) in image.pixels.iter_mut().zip(&other.pixels) {
+        *pixel += *other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::image::Image;
+    use crate::post::{Bloom, ChromaticAberration, Glare, PostEffects, Vignette};
+
+    // Dim gray with one very bright pixel in the middle
+    fn point_light() -> Image {
+        let mut image = Image::new(21, 21);
+        for pixel in &mut image.pixels {
+            *pixel = Color::white() * 0.2;
+        }
+        image.set(10, 10, Color::white() * 100.);
+        image
+    }
+
+    #[test]
+    fn no_effects_leave_the_image_alone() {
+        let image = point_light();
+        assert_eq!(PostEffects::default().apply(&image).pixels, image.pixels);
+    }
+
+    #[test]
+    fn bloom_spreads_only_light_above_the_threshold() {
+        let bloom = PostEffects { bloom: Some(Bloom { threshold: 1., strength: 0.5, radius: 2. }), ..Default::default() };
+        let image = point_light();
+        let result = bloom.apply(&image);
+        assert!(result.get(12, 10).x > 1.);
+        assert_eq!(result.get(0, 0), image.get(0, 0)); // Too far for the glow to reach
+        // Half of the 99 units above the threshold, blurred, added on top of the 100 there already were
+        let total_added: f64 = result.pixels.iter().zip(&image.pixels).map(|(a, b)| a.x - b.x).sum();
+        assert!((total_added - 49.5).abs() < 0.1); // Not exact, since the blur renormalizes near the edges
+
+        let dim = Image::new(4, 4);
+        assert_eq!(bloom.apply(&dim).pixels, dim.pixels);
+    }
+
+    #[test]
+    fn glare_draws_streaks_along_its_directions() {
+        let glare = Glare { threshold: 1., strength: 0.2, streaks: 2, length: 8, falloff: 0.9, rotation: 0. };
+        let result = PostEffects { glare: Some(glare), ..Default::default() }.apply(&point_light());
+        // A cross: brighter along the row and column of the light than on the diagonal
+        assert!(result.get(15, 10).x > 0.3);
+        assert!(result.get(10, 5).x > 0.3);
+        assert!((result.get(15, 15).x - 0.2).abs() < 1e-12);
+        assert_eq!(result.get(15, 10), result.get(10, 15));
+    }
+
+    #[test]
+    fn vignette_darkens_the_corners_only() {
+        let mut image = Image::new(20, 10);
+        for pixel in &mut image.pixels {
+            *pixel = Color::white();
+        }
+        let result = PostEffects { vignette: Some(Vignette { strength: 0.5 }), ..Default::default() }.apply(&image);
+        assert!(result.get(10, 5).x > 0.99);
+        assert!(result.get(0, 0).x < 0.6);
+        assert_eq!(result.get(0, 0), result.get(19, 9));
+    }
+
+    #[test]
+    fn chromatic_aberration_separates_red_and_blue_towards_the_edges() {
+        // A white vertical line near the right edge
+        let mut image = Image::new(40, 20);
+        for y in 0..20 {
+            image.set(35, y, Color::white());
+        }
+        let effects = PostEffects { chromatic_aberration: Some(ChromaticAberration { strength: 4. }), ..Default::default() };
+        let result = effects.apply(&image);
+        let brightest = |channel: fn(&Color) -> f64| (0..40).max_by(|&a, &b| channel(&result.get(a, 10)).total_cmp(&channel(&result.get(b, 10)))).unwrap();
+        assert_eq!(brightest(|color| color.y), 35);
+        assert!(brightest(|color| color.x) > 35);
+        assert!(brightest(|color| color.z) < 35);
+
+        // Deterministic
+        assert_eq!(effects.apply(&image).pixels, result.pixels);
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Progress {
+    // Rows of every pass together in a progressive render
+    pub completed_rows: u64,
+    pub total_rows: u64,
+    pub elapsed: Duration,
+    pub rays_traced: u64,
+}
+
+impl Progress {
+    pub fn fraction_done(&self) -> f64 {
+        if self.total_rows == 0 {
+            return 1.;
+        }
+        self.completed_rows as f64 / self.total_rows as f64
+    }
+
+    pub fn percent_done(&self) -> f64 {
+        self.fraction_done() * 100.
+    }
+
+    // Linear extrapolation from the rows rendered so far, None until the first row is done
+    pub fn eta(&self) -> Option<Duration> {
+        if self.completed_rows == 0 {
+            return None;
+        }
+        let remaining_rows = self.total_rows - self.completed_rows;
+        let per_row = self.elapsed.as_secs_f64() / self.completed_rows as f64;
+        Some(Duration::from_secs_f64(per_row * remaining_rows as f64))
+    }
+
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0. {
+            return 0.;
+        }
+        self.rays_traced as f64 / seconds
+    }
+}
+
+pub trait ProgressObserver {
+    fn on_progress(&mut self, progress: &Progress);
+}
+
+impl<F: FnMut(&Progress)> ProgressObserver for F {
+    fn on_progress(&mut self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+// Observer for callers that don't care about progress
+pub struct NoProgress;
+
+impl ProgressObserver for NoProgress {
+    fn on_progress(&mut self, _progress: &Progress) {}
+}
+
+// Cheaply clonable flag that can be handed to another thread to abort a render.
+// The render checks it between rows, so what has been rendered so far is kept.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::progress::{CancellationToken, Progress};
+
+    #[test]
+    fn eta_extrapolates_from_completed_rows() {
+        let progress = Progress {
+            completed_rows: 25,
+            total_rows: 100,
+            elapsed: Duration::from_secs(10),
+            rays_traced: 1000,
+        };
+        assert_eq!(progress.percent_done(), 25.);
+        assert_eq!(progress.eta(), Some(Duration::from_secs(30)));
+        assert_eq!(progress.rays_per_second(), 100.);
+    }
+
+    #[test]
+    fn cancellation_is_shared_between_clones() {
+        let token = CancellationToken::new();
+        let handle = token.clone();
+        assert!(!token.is_cancelled());
+        handle.cancel();
+        assert!(token.is_cancelled());
+    }
+}
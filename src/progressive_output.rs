@@ -0,0 +1,153 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::exr::{Compression, PixelType};
+use crate::image::Image;
+use crate::tone_mapping::OutputTransform;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    Ppm(OutputTransform),
+    Exr(PixelType, Compression),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlushSchedule {
+    // At the first pass, then at the first pass that ends at least this long after the last write
+    Interval(Duration),
+    // After every this many passes
+    EveryPasses(u32),
+}
+
+// Keeps an image file up to date with a progressive render (see `Camera::render_progressive`).
+// Every write goes to a temporary file next to `path` that is then renamed over it, so a viewer
+// that reloads on change never sees a half-written image.
+pub struct ProgressiveOutput {
+    path: PathBuf,
+    format: OutputFormat,
+    schedule: FlushSchedule,
+    last_flush: Option<Instant>,
+}
+
+impl ProgressiveOutput {
+    pub fn new(path: impl Into<PathBuf>, format: OutputFormat, schedule: FlushSchedule) -> ProgressiveOutput {
+        ProgressiveOutput { path: path.into(), format, schedule, last_flush: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Call after every pass; writes `image` if the schedule says it's time. Returns whether it did.
+    pub fn on_pass(&mut self, pass: u32, image: &Image) -> io::Result<bool> {
+        let due = match self.schedule {
+            FlushSchedule::Interval(interval) => self.last_flush.is_none_or(|last| last.elapsed() >= interval),
+            FlushSchedule::EveryPasses(passes) => pass.is_multiple_of(passes.max(1)),
+        };
+        if due {
+            self.flush(image)?;
+        }
+        Ok(due)
+    }
+
+    // Writes `image` now, whatever the schedule; for the finished render
+    pub fn flush(&mut self, image: &Image) -> io::Result<()> {
+        let mut file_name = self.path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".tmp");
+        let temporary = self.path.with_file_name(file_name);
+
+        let write = || -> io::Result<()> {
+            let file = File::create(&temporary)?;
+            let mut out = BufWriter::new(file);
+            match self.format {
+                OutputFormat::Ppm(transform) => image.write_ppm(&mut out, &transform)?,
+                OutputFormat::Exr(pixel_type, compression) => image.write_exr(&mut out, pixel_type, compression)?,
+            }
+            let file = out.into_inner().map_err(|error| error.into_error())?;
+            file.sync_all()?;
+            fs::rename(&temporary, &self.path)
+        };
+        if let Err(error) = write() {
+            // Don't leave a half-written temporary file behind; it may not even exist
+            let _ = fs::remove_file(&temporary);
+            return Err(error);
+        }
+
+        self.last_flush = Some(Instant::now());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use crate::color::Color;
+    use crate::image::Image;
+    use crate::progressive_output::{FlushSchedule, OutputFormat, ProgressiveOutput};
+    use crate::tone_mapping::OutputTransform;
+
+    fn scratch_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("progressive-output-{}-{name}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn flushes_every_n_passes_and_replaces_the_file() {
+        let directory = scratch_directory("every-passes");
+        let path = directory.join("preview.ppm");
+        let format = OutputFormat::Ppm(OutputTransform::default());
+        let mut output = ProgressiveOutput::new(&path, format, FlushSchedule::EveryPasses(2));
+
+        let mut image = Image::new(2, 1);
+        let mut flushed = Vec::new();
+        for pass in 1..=5 {
+            image.set(0, 0, Color::white() * (pass as f64 / 5.));
+            if output.on_pass(pass, &image).unwrap() {
+                flushed.push(pass);
+            }
+        }
+        assert_eq!(flushed, [2, 4]);
+
+        let mut expected = Vec::new();
+        image.set(0, 0, Color::white() * 0.8);
+        image.write_ppm(&mut expected, &OutputTransform::default()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), expected);
+        // Only the image itself is left behind
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn interval_schedule_writes_the_first_pass_right_away() {
+        let directory = scratch_directory("interval");
+        let path = directory.join("preview.ppm");
+        let format = OutputFormat::Ppm(OutputTransform::default());
+        let mut output = ProgressiveOutput::new(&path, format, FlushSchedule::Interval(Duration::from_secs(3600)));
+
+        let image = Image::new(1, 1);
+        assert!(output.on_pass(1, &image).unwrap());
+        assert!(!output.on_pass(2, &image).unwrap());
+        assert!(path.exists());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn failed_flush_leaves_no_temporary_file() {
+        let directory = scratch_directory("failed-flush");
+        // The rename fails because a directory is in the way
+        let path = directory.join("preview.ppm");
+        fs::create_dir(&path).unwrap();
+        let format = OutputFormat::Ppm(OutputTransform::default());
+        let mut output = ProgressiveOutput::new(&path, format, FlushSchedule::EveryPasses(1));
+
+        assert!(output.flush(&Image::new(1, 1)).is_err());
+        assert!(!directory.join("preview.ppm.tmp").exists());
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
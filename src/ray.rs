@@ -4,10 +4,19 @@ use crate::vec3::{Point3, Vec3};
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    pub time: f64, // Moment within the camera's shutter interval the ray samples
 }
 
 impl Ray {
+    pub fn new(origin: Point3, direction: Vec3) -> Ray {
+        Ray { origin, direction, time: 0. }
+    }
+
+    pub fn with_time(origin: Point3, direction: Vec3, time: f64) -> Ray {
+        Ray { origin, direction, time }
+    }
+
     pub fn at(&self, t: f64) -> Point3 {
-        &self.origin + &self.direction * t
+        self.origin + self.direction * t
     }
 }
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+use crate::vec3::{Point3, Vec3};
+
+// Signed distance to a surface: negative inside, positive outside.
+// Sphere tracing relies on the magnitude never overestimating the true distance.
+pub trait Sdf: Send + Sync {
+    fn distance(&self, point: Point3) -> f64;
+}
+
+impl<F: Fn(Point3) -> f64 + Send + Sync> Sdf for F {
+    fn distance(&self, point: Point3) -> f64 {
+        self(point)
+    }
+}
+
+impl Sdf for Arc<dyn Sdf> {
+    fn distance(&self, point: Point3) -> f64 {
+        self.as_ref().distance(point)
+    }
+}
+
+pub fn sphere(center: Point3, radius: f64) -> impl Sdf {
+    move |p: Point3| (p - center).length() - radius
+}
+
+pub fn cuboid(center: Point3, half_extents: Vec3) -> impl Sdf {
+    move |p: Point3| {
+        let q = (p - center).map(f64::abs) - half_extents;
+        let outside = q.map(|n| n.max(0.)).length();
+        let inside = q.x.max(q.y).max(q.z).min(0.);
+        outside + inside
+    }
+}
+
+// Ring in the xz plane around `center`
+pub fn torus(center: Point3, major_radius: f64, minor_radius: f64) -> impl Sdf {
+    move |p: Point3| {
+        let p = p - center;
+        let ring = (p.x * p.x + p.z * p.z).sqrt() - major_radius;
+        (ring * ring + p.y * p.y).sqrt() - minor_radius
+    }
+}
+
+pub fn translate(sdf: impl Sdf, offset: Vec3) -> impl Sdf {
+    move |p: Point3| sdf.distance(p - offset)
+}
+
+pub fn union(a: impl Sdf, b: impl Sdf) -> impl Sdf {
+    move |p: Point3| a.distance(p).min(b.distance(p))
+}
+
+pub fn intersection(a: impl Sdf, b: impl Sdf) -> impl Sdf {
+    move |p: Point3| a.distance(p).max(b.distance(p))
+}
+
+pub fn difference(a: impl Sdf, b: impl Sdf) -> impl Sdf {
+    move |p: Point3| a.distance(p).max(-b.distance(p))
+}
+
+// Union that blends the surfaces together within roughly `k` of where they meet
+// (polynomial smooth minimum)
+pub fn smooth_union(a: impl Sdf, b: impl Sdf, k: f64) -> impl Sdf {
+    move |p: Point3| {
+        let da = a.distance(p);
+        let db = b.distance(p);
+        let h = (0.5 + 0.5 * (db - da) / k).clamp(0., 1.);
+        db + (da - db) * h - k * h * (1. - h)
+    }
+}
+
+// Infinitely tiles space with copies of `sdf`, one per cell of size `period` centered on the origin.
+// Exact as long as the shape fits inside its cell.
+pub fn repeat(sdf: impl Sdf, period: Vec3) -> impl Sdf {
+    move |p: Point3| {
+        let wrap = |n: f64, period: f64| n - period * (n / period).round();
+        sdf.distance(Vec3::new(wrap(p.x, period.x), wrap(p.y, period.y), wrap(p.z, period.z)))
+    }
+}
+
+// Rotates each horizontal slice around the y axis by `radians_per_unit` times its height.
+// This stretches distances, so trace it with a reduced step scale.
+pub fn twist(sdf: impl Sdf, radians_per_unit: f64) -> impl Sdf {
+    move |p: Point3| {
+        let (sin, cos) = (radians_per_unit * p.y).sin_cos();
+        sdf.distance(Vec3::new(cos * p.x - sin * p.z, p.y, sin * p.x + cos * p.z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sdf::{cuboid, repeat, smooth_union, sphere, Sdf};
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn cuboid_distance_inside_and_outside() {
+        let unit = cuboid(Vec3::zero(), Vec3::new(1., 1., 1.));
+        assert_eq!(unit.distance(Vec3::new(3., 0., 0.)), 2.);
+        assert_eq!(unit.distance(Vec3::new(0.5, 0., 0.)), -0.5);
+    }
+
+    #[test]
+    fn smooth_union_bulges_between_shapes() {
+        let a = sphere(Vec3::new(-1., 0., 0.), 0.9);
+        let b = sphere(Vec3::new(1., 0., 0.), 0.9);
+        let midpoint = Vec3::zero();
+        assert!(a.distance(midpoint) > 0.);
+        assert!(smooth_union(a, b, 0.5).distance(midpoint) < 0.);
+    }
+
+    #[test]
+    fn repeat_tiles_copies() {
+        let tiled = repeat(sphere(Vec3::zero(), 0.5), Vec3::new(4., 4., 4.));
+        assert_eq!(tiled.distance(Vec3::new(8., -4., 0.)), -0.5);
+    }
+}
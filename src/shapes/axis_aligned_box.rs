@@ -0,0 +1,105 @@
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::hittable_collection::HittableCollection;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shapes::quad::Quad;
+use crate::solid::{Solid, Span};
+use crate::vec3::{Point3, Vec3};
+
+// Box made of six outward-facing quads. Each face gets its own [0, 1] UV square.
+pub struct AxisAlignedBox {
+    min: Point3,
+    max: Point3,
+    sides: HittableCollection,
+}
+
+impl AxisAlignedBox {
+    pub fn new(a: Point3, b: Point3, material: Arc<dyn Material>) -> AxisAlignedBox {
+        let min = Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let max = Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+        let dx = Vec3::new(max.x - min.x, 0., 0.);
+        let dy = Vec3::new(0., max.y - min.y, 0.);
+        let dz = Vec3::new(0., 0., max.z - min.z);
+
+        let mut sides = HittableCollection::new();
+        let mut side = |corner: Point3, u: Vec3, v: Vec3| {
+            sides.add(Box::new(Quad::new(corner, u, v, material.clone())));
+        };
+        side(Vec3::new(min.x, min.y, max.z), dx, dy); // front
+        side(Vec3::new(max.x, min.y, max.z), -dz, dy); // right
+        side(Vec3::new(max.x, min.y, min.z), -dx, dy); // back
+        side(Vec3::new(min.x, min.y, min.z), dz, dy); // left
+        side(Vec3::new(min.x, max.y, max.z), dx, -dz); // top
+        side(Vec3::new(min.x, min.y, min.z), dx, dz); // bottom
+
+        AxisAlignedBox { min, max, sides }
+    }
+
+    pub fn min(&self) -> Point3 {
+        self.min
+    }
+
+    pub fn max(&self) -> Point3 {
+        self.max
+    }
+}
+
+impl Hittable for AxisAlignedBox {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        self.sides.hit(ray, t_interval)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
+}
+
+impl Solid for AxisAlignedBox {
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        let hits = self
+            .sides
+            .objects
+            .iter()
+            .filter_map(|side| side.hit(ray, Interval::UNIVERSE))
+            .collect();
+        Span::convex(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::axis_aligned_box::AxisAlignedBox;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn every_face_normal_points_outwards() {
+        let cube = AxisAlignedBox::new(
+            Vec3::new(-1., -1., -1.),
+            Vec3::new(1., 1., 1.),
+            Arc::new(Lambertian::new(Color::white())),
+        );
+        let axes = [
+            Vec3::new(1., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            Vec3::new(0., 0., 1.),
+        ];
+        for axis in axes {
+            for outward in [axis, -axis] {
+                let ray = Ray::new(3. * outward, -outward);
+                let hit = cube.hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+                assert_eq!(hit.t, 2.);
+                assert_eq!(hit.normal, outward);
+            }
+        }
+    }
+}
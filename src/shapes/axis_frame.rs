@@ -0,0 +1,59 @@
+use std::f64::consts::PI;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// Orthonormal frame for shapes built around an axis. In local coordinates the axis is +y,
+// which lets the intersection code be written for the upright shape only.
+#[derive(Copy, Clone)]
+pub(crate) struct AxisFrame {
+    pub origin: Point3,
+    pub axis: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+}
+
+impl AxisFrame {
+    pub fn new(origin: Point3, axis: Vec3) -> AxisFrame {
+        let axis = axis.normalize();
+        let (tangent, bitangent) = axis.orthonormal_basis();
+        AxisFrame { origin, axis, tangent, bitangent }
+    }
+
+    pub fn local_vector(&self, vector: Vec3) -> Vec3 {
+        Vec3::new(
+            Vec3::dot(&vector, &self.tangent),
+            Vec3::dot(&vector, &self.axis),
+            Vec3::dot(&vector, &self.bitangent),
+        )
+    }
+
+    pub fn local_ray(&self, ray: &Ray) -> Ray {
+        Ray::with_time(self.local_vector(ray.origin - self.origin), self.local_vector(ray.direction), ray.time)
+    }
+
+    pub fn world_vector(&self, vector: Vec3) -> Vec3 {
+        vector.x * self.tangent + vector.y * self.axis + vector.z * self.bitangent
+    }
+}
+
+// Angle around the local y axis scaled to [0, 1], matching the convention of `sphere_uv`
+pub(crate) fn azimuth_uv(local: Vec3) -> f64 {
+    (f64::atan2(-local.z, local.x) + PI) / (2. * PI)
+}
+
+// Unit vector pointing away from the local y axis at azimuth `u`; the inverse of `azimuth_uv`
+pub(crate) fn azimuth_direction(u: f64) -> Vec3 {
+    let (sin, cos) = (2. * PI * u).sin_cos();
+    Vec3::new(-cos, 0., sin)
+}
+
+// How a local point moves as its `azimuth_uv` grows
+pub(crate) fn azimuth_tangent(local: Vec3) -> Vec3 {
+    2. * PI * Vec3::new(local.z, 0., -local.x)
+}
+
+// Tangents of polar UVs on a disk of `radius` around the local y axis, where u is the azimuth and v
+// the distance from the axis as a fraction of the radius
+pub(crate) fn polar_tangents(local: Vec3, u: f64, radius: f64) -> (Vec3, Vec3) {
+    (azimuth_tangent(local), radius * azimuth_direction(u))
+}
@@ -0,0 +1,152 @@
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::polynomial::solve_quadratic;
+use crate::ray::Ray;
+use crate::shapes::axis_frame::{azimuth_tangent, azimuth_uv, AxisFrame};
+use crate::vec3::{Point3, Vec3};
+
+// Every point within `radius` of the segment from `start` to `end`: a cylinder with hemispherical ends.
+// u wraps around the axis, v runs along the whole length including the end caps.
+#[derive(Clone)]
+pub struct Capsule {
+    frame: AxisFrame,
+    length: f64,
+    radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Capsule {
+    pub fn new(start: Point3, end: Point3, radius: f64, material: Arc<dyn Material>) -> Capsule {
+        assert!(start != end && radius > 0., "a capsule needs distinct end points and a positive radius");
+        let segment = end - start;
+        Capsule { frame: AxisFrame::new(start, segment), length: segment.length(), radius, material }
+    }
+
+    pub fn start(&self) -> Point3 {
+        self.frame.origin
+    }
+
+    pub fn end(&self) -> Point3 {
+        self.frame.origin + self.length * self.frame.axis
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl Hittable for Capsule {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        let local = self.frame.local_ray(ray);
+        let Ray { origin: o, direction: d, .. } = local;
+        let r2 = self.radius * self.radius;
+        let mut closest: Option<f64> = None;
+        let mut consider = |t: f64| {
+            if t_interval.surrounds(t) && closest.is_none_or(|closest_t| t < closest_t) {
+                closest = Some(t);
+            }
+        };
+
+        let a = d.x * d.x + d.z * d.z;
+        if a > 1e-12 {
+            let b = 2. * (o.x * d.x + o.z * d.z);
+            let c = o.x * o.x + o.z * o.z - r2;
+            for t in solve_quadratic(a, b, c) {
+                if (0. ..=self.length).contains(&local.at(t).y) {
+                    consider(t);
+                }
+            }
+        }
+
+        // Each end sphere only counts on its own side of the cylinder
+        for end_y in [0., self.length] {
+            let oc = o - Vec3::new(0., end_y, 0.);
+            let a = d.length_squared();
+            let b = 2. * Vec3::dot(&oc, &d);
+            let c = oc.length_squared() - r2;
+            for t in solve_quadratic(a, b, c) {
+                let y = local.at(t).y;
+                if (end_y == 0. && y <= 0.) || (end_y == self.length && y >= self.length) {
+                    consider(t);
+                }
+            }
+        }
+
+        let t = closest?;
+        let p = local.at(t);
+        let nearest_on_segment = Vec3::new(0., p.y.clamp(0., self.length), 0.);
+        let normal = (p - nearest_on_segment) / self.radius;
+        // v climbs the height, so on the end caps it follows the meridian, which gets steeper
+        // towards the poles; at the poles themselves it has no direction
+        let meridian = Vec3::new(0., 1., 0.) - normal.y * normal;
+        let steepness = 1. - normal.y * normal.y;
+        let dpdv = if steepness < 1e-12 {
+            Vec3::zero()
+        } else {
+            (self.length + 2. * self.radius) / steepness * meridian
+        };
+        let hit = Hit::new(
+            ray,
+            t,
+            self.frame.world_vector(normal),
+            azimuth_uv(p),
+            (p.y + self.radius) / (self.length + 2. * self.radius),
+            self.material.clone(),
+        );
+        Some(hit.with_tangents(self.frame.world_vector(azimuth_tangent(p)), self.frame.world_vector(dpdv)))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::around_sphere(self.start(), self.radius).union(&Aabb::around_sphere(self.end(), self.radius)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::capsule::Capsule;
+    use crate::vec3::Vec3;
+
+    fn capsule() -> Capsule {
+        Capsule::new(Vec3::zero(), Vec3::new(0., 2., 0.), 0.5, Arc::new(Lambertian::new(Color::white())))
+    }
+
+    #[test]
+    fn ray_down_the_axis_hits_the_end_cap() {
+        let ray = Ray::new(Vec3::new(0., 5., 0.), Vec3::new(0., -1., 0.));
+        let hit = capsule().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 2.5).abs() < 1e-9);
+        assert!((hit.normal - Vec3::new(0., 1., 0.)).length() < 1e-9);
+    }
+
+    #[test]
+    fn grazing_the_seam_between_side_and_cap() {
+        // Tangent to the surface exactly where the cylinder meets the top hemisphere
+        let ray = Ray::new(Vec3::new(0.5 - 1e-9, 2., 5.), Vec3::new(0., 0., -1.));
+        let hit = capsule().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!(hit.normal.x > 0.99);
+
+        let miss = Ray::new(Vec3::new(0.5 + 1e-9, 2., 5.), Vec3::new(0., 0., -1.));
+        assert!(capsule().hit(&miss, Interval::new(0.001, f64::INFINITY)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "a capsule needs distinct end points and a positive radius")]
+    fn rejects_a_capsule_without_length() {
+        Capsule::new(Vec3::zero(), Vec3::zero(), 0.5, Arc::new(Lambertian::new(Color::white())));
+    }
+
+    #[test]
+    #[should_panic(expected = "a capsule needs distinct end points and a positive radius")]
+    fn rejects_a_capsule_without_radius() {
+        Capsule::new(Vec3::zero(), Vec3::new(0., 2., 0.), 0., Arc::new(Lambertian::new(Color::white())));
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::polynomial::solve_quadratic;
+use crate::ray::Ray;
+use crate::shapes::axis_frame::{azimuth_direction, azimuth_tangent, azimuth_uv, polar_tangents, AxisFrame};
+use crate::vec3::{Point3, Vec3};
+
+// Cone with its base disk on `base` and its apex `height` along `axis`.
+// UVs follow `Cylinder`: around the axis and up the height, polar on the base cap.
+#[derive(Clone)]
+pub struct Cone {
+    frame: AxisFrame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    pub material: Arc<dyn Material>,
+}
+
+impl Cone {
+    pub fn new(base: Point3, axis: Vec3, radius: f64, height: f64, capped: bool, material: Arc<dyn Material>) -> Cone {
+        assert!(radius > 0. && height > 0., "a cone needs a positive radius and height");
+        Cone { frame: AxisFrame::new(base, axis), radius, height, capped, material }
+    }
+
+    pub fn base(&self) -> Point3 {
+        self.frame.origin
+    }
+
+    pub fn apex(&self) -> Point3 {
+        self.frame.origin + self.height * self.frame.axis
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        let local = self.frame.local_ray(ray);
+        let Ray { origin: o, direction: d, .. } = local;
+        // t, normal, u, v and the tangents, all local
+        let mut closest: Option<(f64, Vec3, f64, f64, Vec3, Vec3)> = None;
+        let mut consider = |t: f64, normal: Vec3, u: f64, v: f64, (dpdu, dpdv): (Vec3, Vec3)| {
+            if t_interval.surrounds(t) && closest.is_none_or(|(closest_t, ..)| t < closest_t) {
+                closest = Some((t, normal, u, v, dpdu, dpdv));
+            }
+        };
+
+        // x^2 + z^2 = k^2 (h - y)^2, where k is the radius lost per unit of height
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let a = d.x * d.x + d.z * d.z - k2 * d.y * d.y;
+        let b = 2. * (o.x * d.x + o.z * d.z + k2 * (self.height - o.y) * d.y);
+        let c = o.x * o.x + o.z * o.z - k2 * (self.height - o.y) * (self.height - o.y);
+        // A ray parallel to the slant makes `a` vanish; solve_quadratic then falls back to the linear root
+        for t in solve_quadratic(a, b, c) {
+            let p = local.at(t);
+            if (0. ..=self.height).contains(&p.y) {
+                let radial = (p.x * p.x + p.z * p.z).sqrt();
+                let normal = if radial < 1e-12 {
+                    // The apex has no unique normal, so use the axis
+                    Vec3::new(0., 1., 0.)
+                } else {
+                    Vec3::new(p.x, k * radial, p.z).normalize()
+                };
+                // Going up the side moves towards the axis as well
+                let u = azimuth_uv(p);
+                let dpdv = Vec3::new(0., self.height, 0.) - self.radius * azimuth_direction(u);
+                consider(t, normal, u, p.y / self.height, (azimuth_tangent(p), dpdv));
+            }
+        }
+
+        if self.capped && d.y.abs() > 1e-12 {
+            let t = -o.y / d.y;
+            let p = local.at(t);
+            let distance_squared = p.x * p.x + p.z * p.z;
+            if distance_squared <= self.radius * self.radius {
+                let u = azimuth_uv(p);
+                let tangents = polar_tangents(p, u, self.radius);
+                consider(t, Vec3::new(0., -1., 0.), u, distance_squared.sqrt() / self.radius, tangents);
+            }
+        }
+
+        let (t, normal, u, v, dpdu, dpdv) = closest?;
+        let hit = Hit::new(ray, t, self.frame.world_vector(normal), u, v, self.material.clone());
+        Some(hit.with_tangents(self.frame.world_vector(dpdu), self.frame.world_vector(dpdv)))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::around_sphere(self.base(), self.radius).union(&Aabb::around_points(&[self.apex()])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::cone::Cone;
+    use crate::vec3::Vec3;
+
+    fn cone(capped: bool) -> Cone {
+        Cone::new(Vec3::zero(), Vec3::new(0., 1., 0.), 1., 1., capped, Arc::new(Lambertian::new(Color::white())))
+    }
+
+    #[test]
+    fn ray_down_the_axis_hits_the_apex() {
+        let ray = Ray::new(Vec3::new(0., 3., 0.), Vec3::new(0., -1., 0.));
+        let hit = cone(true).hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 2.).abs() < 1e-9);
+        assert_eq!(hit.normal, Vec3::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn side_normal_is_perpendicular_to_the_slant() {
+        let ray = Ray::new(Vec3::new(5., 0.5, 0.), Vec3::new(-1., 0., 0.));
+        let hit = cone(true).hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.location - Vec3::new(0.5, 0.5, 0.)).length() < 1e-9);
+        let slant = Vec3::new(-1., 1., 0.);
+        assert!(Vec3::dot(&hit.normal, &slant).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ray_parallel_to_the_slant() {
+        // Parallel to the left slant but half a unit beside it, so the quadratic degenerates to a
+        // linear equation with one root: in through the open base, out through the right slant
+        let ray = Ray::new(Vec3::new(-1.5, -1., 0.), Vec3::new(1., 1., 0.));
+        let hit = cone(false).hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 1.75).abs() < 1e-9);
+        assert!((hit.location - Vec3::new(0.25, 0.75, 0.)).length() < 1e-9);
+        assert!((hit.normal - Vec3::new(1., 1., 0.).normalize()).length() < 1e-9);
+    }
+
+    #[test]
+    fn grazing_the_side() {
+        // At y = 0.5 the cone's radius is 0.5
+        let grazing = |x: f64| Ray::new(Vec3::new(x, 0.5, 5.), Vec3::new(0., 0., -1.));
+        assert!(cone(true).hit(&grazing(0.5 + 1e-9), Interval::new(0.001, f64::INFINITY)).is_none());
+
+        let tangent = cone(true).hit(&grazing(0.5), Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((tangent.t - 5.).abs() < 1e-9);
+        assert!((tangent.normal - Vec3::new(1., 1., 0.).normalize()).length() < 1e-9);
+
+        let hit = cone(true).hit(&grazing(0.5 - 1e-9), Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!(hit.normal.x > 0.7);
+        assert!((hit.normal.length() - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "a cone needs a positive radius and height")]
+    fn rejects_a_flat_cone() {
+        Cone::new(Vec3::zero(), Vec3::new(0., 1., 0.), 1., 0., true, Arc::new(Lambertian::new(Color::white())));
+    }
+
+    #[test]
+    #[should_panic(expected = "a cone needs a positive radius and height")]
+    fn rejects_a_cone_without_radius() {
+        Cone::new(Vec3::zero(), Vec3::new(0., 1., 0.), 0., 1., true, Arc::new(Lambertian::new(Color::white())));
+    }
+}
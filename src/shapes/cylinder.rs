@@ -0,0 +1,168 @@
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::polynomial::solve_quadratic;
+use crate::ray::Ray;
+use crate::shapes::axis_frame::{azimuth_tangent, azimuth_uv, polar_tangents, AxisFrame};
+use crate::solid::{Solid, Span};
+use crate::vec3::{Point3, Vec3};
+
+// Cylinder standing on `base` and extending `height` along `axis`.
+// Side UVs wrap around the axis and run up the height; cap UVs are polar like `Disk`.
+#[derive(Clone)]
+pub struct Cylinder {
+    frame: AxisFrame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    pub material: Arc<dyn Material>,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, axis: Vec3, radius: f64, height: f64, capped: bool, material: Arc<dyn Material>) -> Cylinder {
+        assert!(radius > 0. && height > 0., "a cylinder needs a positive radius and height");
+        Cylinder { frame: AxisFrame::new(base, axis), radius, height, capped, material }
+    }
+
+    pub fn base(&self) -> Point3 {
+        self.frame.origin
+    }
+
+    pub fn axis(&self) -> Vec3 {
+        self.frame.axis
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    pub fn capped(&self) -> bool {
+        self.capped
+    }
+}
+
+impl Cylinder {
+    // Every crossing of the surface along the whole line of the ray, in no particular order
+    fn intersections(&self, ray: &Ray, with_caps: bool) -> Vec<Hit> {
+        let local = self.frame.local_ray(ray);
+        let Ray { origin: o, direction: d, .. } = local;
+        let mut hits = Vec::new();
+        let mut add = |t: f64, normal: Vec3, u: f64, v: f64, (dpdu, dpdv): (Vec3, Vec3)| {
+            let hit = Hit::new(ray, t, self.frame.world_vector(normal), u, v, self.material.clone());
+            hits.push(hit.with_tangents(self.frame.world_vector(dpdu), self.frame.world_vector(dpdv)));
+        };
+
+        // Rays parallel to the axis can only hit the caps
+        let a = d.x * d.x + d.z * d.z;
+        if a > 1e-12 {
+            let b = 2. * (o.x * d.x + o.z * d.z);
+            let c = o.x * o.x + o.z * o.z - self.radius * self.radius;
+            for t in solve_quadratic(a, b, c) {
+                let p = local.at(t);
+                if (0. ..=self.height).contains(&p.y) {
+                    let normal = Vec3::new(p.x, 0., p.z) / self.radius;
+                    let tangents = (azimuth_tangent(p), Vec3::new(0., self.height, 0.));
+                    add(t, normal, azimuth_uv(p), p.y / self.height, tangents);
+                }
+            }
+        }
+
+        if with_caps && d.y.abs() > 1e-12 {
+            for (cap_y, normal_y) in [(0., -1.), (self.height, 1.)] {
+                let t = (cap_y - o.y) / d.y;
+                let p = local.at(t);
+                let distance_squared = p.x * p.x + p.z * p.z;
+                if distance_squared <= self.radius * self.radius {
+                    let normal = Vec3::new(0., normal_y, 0.);
+                    let u = azimuth_uv(p);
+                    add(t, normal, u, distance_squared.sqrt() / self.radius, polar_tangents(p, u, self.radius));
+                }
+            }
+        }
+        hits
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        self.intersections(ray, self.capped)
+            .into_iter()
+            .filter(|hit| t_interval.surrounds(hit.t))
+            .min_by(|a, b| a.t.total_cmp(&b.t))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let top = self.base() + self.height * self.axis();
+        Some(Aabb::around_sphere(self.base(), self.radius).union(&Aabb::around_sphere(top, self.radius)))
+    }
+}
+
+// As a solid the cylinder is always closed, even when its caps aren't rendered on their own
+impl Solid for Cylinder {
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        Span::convex(self.intersections(ray, true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::cylinder::Cylinder;
+    use crate::vec3::Vec3;
+
+    fn cylinder(capped: bool) -> Cylinder {
+        Cylinder::new(Vec3::zero(), Vec3::new(0., 1., 0.), 1., 2., capped, Arc::new(Lambertian::new(Color::white())))
+    }
+
+    #[test]
+    fn ray_parallel_to_axis_only_hits_caps() {
+        let ray = Ray::new(Vec3::new(0.5, 5., 0.), Vec3::new(0., -1., 0.));
+        assert!(cylinder(false).hit(&ray, Interval::new(0.001, f64::INFINITY)).is_none());
+
+        let hit = cylinder(true).hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert_eq!(hit.t, 3.);
+        assert_eq!(hit.normal, Vec3::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn grazing_the_side() {
+        let grazing = |x: f64| Ray::new(Vec3::new(x, 1., 5.), Vec3::new(0., 0., -1.));
+        assert!(cylinder(true).hit(&grazing(1. + 1e-9), Interval::new(0.001, f64::INFINITY)).is_none());
+
+        let hit = cylinder(true).hit(&grazing(1. - 1e-9), Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!(hit.normal.x > 0.99);
+        assert!((hit.normal.length() - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ray_through_the_open_top_hits_the_inside_wall() {
+        // Passes just above the near rim, then hits the far wall from the inside
+        let ray = Ray::new(Vec3::new(-1.5, 3., 0.), Vec3::new(1., -1., 0.));
+        let hit = cylinder(false).hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.location - Vec3::new(1., 0.5, 0.)).length() < 1e-9);
+        assert_eq!(hit.normal, Vec3::new(1., 0., 0.));
+    }
+
+    #[test]
+    #[should_panic(expected = "a cylinder needs a positive radius and height")]
+    fn rejects_a_flat_cylinder() {
+        Cylinder::new(Vec3::zero(), Vec3::new(0., 1., 0.), 1., 0., true, Arc::new(Lambertian::new(Color::white())));
+    }
+
+    #[test]
+    #[should_panic(expected = "a cylinder needs a positive radius and height")]
+    fn rejects_a_cylinder_without_radius() {
+        Cylinder::new(Vec3::zero(), Vec3::new(0., 1., 0.), -1., 2., true, Arc::new(Lambertian::new(Color::white())));
+    }
+}
@@ -0,0 +1,124 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// Flat circle facing along `normal`. u is the angle around the center, v the distance from it,
+// both scaled to [0, 1].
+#[derive(Clone)]
+pub struct Disk {
+    center: Point3,
+    normal: Vec3,
+    radius: f64,
+    tangent: Vec3,
+    bitangent: Vec3,
+    pub material: Arc<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, material: Arc<dyn Material>) -> Disk {
+        let normal = normal.normalize();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        Disk { center, normal, radius, tangent, bitangent, material }
+    }
+
+    pub fn center(&self) -> Point3 {
+        self.center
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        let denominator = Vec3::dot(&self.normal, &ray.direction);
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = Vec3::dot(&self.normal, &(self.center - ray.origin)) / denominator;
+        if !t_interval.surrounds(t) {
+            return None;
+        }
+
+        let location = ray.at(t);
+        let planar = location - self.center;
+        let distance_squared = planar.length_squared();
+        if distance_squared > self.radius * self.radius {
+            return None;
+        }
+
+        let x = Vec3::dot(&planar, &self.tangent);
+        let y = Vec3::dot(&planar, &self.bitangent);
+        let u = (f64::atan2(y, x) + PI) / (2. * PI);
+        // u turns around the center and v moves straight out from it, starting at -tangent
+        let (sin, cos) = (2. * PI * u).sin_cos();
+        let dpdu = 2. * PI * (x * self.bitangent - y * self.tangent);
+        let dpdv = -self.radius * (cos * self.tangent + sin * self.bitangent);
+        let hit = Hit::new(ray, t, self.normal, u, distance_squared.sqrt() / self.radius, self.material.clone());
+        Some(hit.with_tangents(dpdu, dpdv))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // A circle of radius r facing n extends r * sqrt(1 - n_i^2) along each axis
+        let extent = self.normal.map(|n| self.radius * (1. - n * n).max(0.).sqrt());
+        Some(Aabb::new(self.center - extent, self.center + extent).padded())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::disk::Disk;
+    use crate::vec3::{Point3, Vec3};
+
+    fn disk() -> Disk {
+        Disk::new(Point3::new(0., 0., -2.), Vec3::new(0., 0., 1.), 2., Arc::new(Lambertian::new(Color::white())))
+    }
+
+    #[test]
+    fn hit_reports_radial_distance_as_v() {
+        let ray = Ray::new(Point3::new(1., 0., 0.), Vec3::new(0., 0., -1.));
+        let hit = disk().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+
+        assert_eq!(hit.t, 2.);
+        assert_eq!(hit.normal, Vec3::new(0., 0., 1.));
+        assert!((hit.v - 0.5).abs() < 1e-12);
+        assert!((0. ..=1.).contains(&hit.u));
+    }
+
+    #[test]
+    fn misses_outside_the_radius_and_parallel_rays() {
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let outside = Ray::new(Point3::new(1.5, 1.5, 0.), Vec3::new(0., 0., -1.));
+        let parallel = Ray::new(Point3::new(0., 0., 0.), Vec3::new(1., 0., 0.));
+
+        assert!(disk().hit(&outside, interval).is_none());
+        assert!(disk().hit(&parallel, interval).is_none());
+    }
+
+    #[test]
+    fn back_face_hits_keep_the_outward_normal() {
+        let ray = Ray::new(Point3::new(0.5, 0., -5.), Vec3::new(0., 0., 1.));
+        let hit = disk().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+
+        assert_eq!(hit.normal, Vec3::new(0., 0., 1.));
+        assert!(!hit.front_face);
+        assert_eq!(hit.oriented_normal(), Vec3::new(0., 0., -1.));
+    }
+}
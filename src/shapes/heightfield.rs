@@ -0,0 +1,398 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// Terrain from a grid of elevations. Sample (i, j) sits at `corner + (i, height, j) * spacing`,
+// each grid cell is split into two triangles, and shading normals are interpolated from per-sample normals.
+// UVs span [0, 1] over the whole grid.
+#[derive(Clone)]
+pub struct Heightfield {
+    corner: Point3,
+    spacing: Vec3,
+    columns: usize,
+    rows: usize,
+    heights: Vec<f64>,
+    normals: Vec<Vec3>,
+    cell_bounds: Vec<(f64, f64)>, // Lowest and highest corner of every cell
+    min_height: f64,
+    max_height: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Heightfield {
+    // `heights` is row-major with `columns` samples per row; there must be at least 2x2 samples
+    pub fn new(corner: Point3, spacing: Vec3, columns: usize, heights: Vec<f64>, material: Arc<dyn Material>) -> Heightfield {
+        assert!(columns >= 2, "a heightfield needs at least 2x2 samples");
+        let rows = heights.len() / columns;
+        assert!(rows >= 2, "a heightfield needs at least 2x2 samples");
+        assert_eq!(rows * columns, heights.len(), "heights must fill whole rows");
+
+        let mut heightfield = Heightfield {
+            corner,
+            spacing,
+            columns,
+            rows,
+            heights,
+            normals: vec![],
+            cell_bounds: vec![],
+            min_height: f64::INFINITY,
+            max_height: f64::NEG_INFINITY,
+            material,
+        };
+        heightfield.normals = (0..rows)
+            .flat_map(|j| (0..columns).map(move |i| (i, j)))
+            .map(|(i, j)| heightfield.sample_normal(i, j))
+            .collect();
+        heightfield.cell_bounds = (0..rows - 1)
+            .flat_map(|j| (0..columns - 1).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                let corners = [
+                    heightfield.height(i, j),
+                    heightfield.height(i + 1, j),
+                    heightfield.height(i, j + 1),
+                    heightfield.height(i + 1, j + 1),
+                ];
+                let low = corners.iter().copied().fold(f64::INFINITY, f64::min);
+                let high = corners.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                (low, high)
+            })
+            .collect();
+        heightfield.min_height = heightfield.cell_bounds.iter().map(|bounds| bounds.0).fold(f64::INFINITY, f64::min);
+        heightfield.max_height = heightfield.cell_bounds.iter().map(|bounds| bounds.1).fold(f64::NEG_INFINITY, f64::max);
+        heightfield
+    }
+
+    // Loads a binary (P5) or plain (P2) PGM. 16-bit files are big-endian as per the format.
+    // Samples are scaled so the format's maximum value maps to `spacing.y`.
+    pub fn from_pgm(reader: &mut impl Read, corner: Point3, spacing: Vec3, material: Arc<dyn Material>) -> io::Result<Heightfield> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let (columns, rows, samples) = parse_pgm(&data)?;
+        if columns < 2 || rows < 2 {
+            return Err(invalid_data("a heightfield needs at least 2x2 samples"));
+        }
+        Ok(Heightfield::new(corner, spacing, columns, samples, material))
+    }
+
+    pub fn load_pgm(path: impl AsRef<Path>, corner: Point3, spacing: Vec3, material: Arc<dyn Material>) -> io::Result<Heightfield> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Heightfield::from_pgm(&mut reader, corner, spacing, material)
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn height(&self, i: usize, j: usize) -> f64 {
+        self.heights[j * self.columns + i] * self.spacing.y
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Point3 {
+        self.corner + Vec3::new(i as f64 * self.spacing.x, self.height(i, j), j as f64 * self.spacing.z)
+    }
+
+    // Central differences, one-sided at the borders
+    fn sample_normal(&self, i: usize, j: usize) -> Vec3 {
+        let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.columns - 1));
+        let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.rows - 1));
+        let slope_x = (self.height(i1, j) - self.height(i0, j)) / ((i1 - i0) as f64 * self.spacing.x);
+        let slope_z = (self.height(i, j1) - self.height(i, j0)) / ((j1 - j0) as f64 * self.spacing.z);
+        Vec3::new(-slope_x, 1., -slope_z).normalize()
+    }
+
+    fn hit_cell(&self, ray: &Ray, i: usize, j: usize, t_interval: Interval) -> Option<Hit> {
+        let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        let triangles = [[corners[0], corners[1], corners[2]], [corners[0], corners[2], corners[3]]];
+
+        let mut closest: Option<Hit> = None;
+        for triangle in triangles {
+            let [a, b, c] = triangle.map(|(i, j)| self.vertex(i, j));
+            let Some((t, beta, gamma)) = intersect_triangle(ray, a, b, c) else {
+                continue;
+            };
+            if !t_interval.surrounds(t) || closest.as_ref().is_some_and(|hit| hit.t <= t) {
+                continue;
+            }
+
+            // The triangle's own normal is the geometric one; the interpolated one only shades
+            let mut normal = Vec3::cross(&(b - a), &(c - a)).normalize();
+            if normal.y < 0. {
+                normal = -normal;
+            }
+            let [na, nb, nc] = triangle.map(|(i, j)| self.normals[j * self.columns + i]);
+            let shading_normal = ((1. - beta - gamma) * na + beta * nb + gamma * nc).normalize();
+
+            // u and v follow the grid's x and z axes, climbing the triangle as they go
+            let size_x = self.spacing.x * (self.columns - 1) as f64;
+            let size_z = self.spacing.z * (self.rows - 1) as f64;
+            let dpdu = size_x * Vec3::new(1., -normal.x / normal.y, 0.);
+            let dpdv = size_z * Vec3::new(0., -normal.z / normal.y, 1.);
+
+            let grid = ray.at(t) - self.corner;
+            let mut hit = Hit::new(ray, t, normal, grid.x / size_x, grid.z / size_z, self.material.clone())
+                .with_tangents(dpdu, dpdv);
+            hit.set_shading_normal(shading_normal);
+            closest = Some(hit);
+        }
+        closest
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        // Work in grid units, where cell (i, j) covers [i, i + 1] x [j, j + 1]
+        let origin = ray.origin - self.corner;
+        let gx = origin.x / self.spacing.x;
+        let gz = origin.z / self.spacing.z;
+        let dx = ray.direction.x / self.spacing.x;
+        let dz = ray.direction.z / self.spacing.z;
+
+        // Clip against the bounding box first
+        let mut clip = t_interval;
+        let slabs = [
+            (gx, dx, 0., (self.columns - 1) as f64),
+            (gz, dz, 0., (self.rows - 1) as f64),
+            (origin.y, ray.direction.y, self.min_height, self.max_height),
+        ];
+        for (o, d, low, high) in slabs {
+            if d.abs() < 1e-12 {
+                if o < low || o > high {
+                    return None;
+                }
+                continue;
+            }
+            let (t0, t1) = ((low - o) / d, (high - o) / d);
+            clip.min = clip.min.max(t0.min(t1));
+            clip.max = clip.max.min(t0.max(t1));
+        }
+        if clip.min > clip.max {
+            return None;
+        }
+
+        // 2D DDA over the cells under the ray, nearest first
+        let max_i = self.columns as isize - 2;
+        let max_j = self.rows as isize - 2;
+        let mut i = ((gx + clip.min * dx).floor() as isize).clamp(0, max_i);
+        let mut j = ((gz + clip.min * dz).floor() as isize).clamp(0, max_j);
+        let axis = |position: f64, direction: f64, cell: isize| -> (isize, f64, f64) {
+            if direction > 0. {
+                (1, (cell as f64 + 1. - position) / direction, 1. / direction)
+            } else if direction < 0. {
+                (-1, (cell as f64 - position) / direction, -1. / direction)
+            } else {
+                (0, f64::INFINITY, f64::INFINITY)
+            }
+        };
+        let (step_i, mut next_x, delta_x) = axis(gx, dx, i);
+        let (step_j, mut next_z, delta_z) = axis(gz, dz, j);
+
+        let mut t = clip.min;
+        while t <= clip.max && (0..=max_i).contains(&i) && (0..=max_j).contains(&j) {
+            let t_exit = next_x.min(next_z).min(clip.max);
+
+            // Skip cells whose height range the ray doesn't pass through
+            let (low, high) = self.cell_bounds[j as usize * (self.columns - 1) + i as usize];
+            let y0 = origin.y + t * ray.direction.y;
+            let y1 = origin.y + t_exit * ray.direction.y;
+            if y0.min(y1) <= high
+                && y0.max(y1) >= low
+                && let Some(hit) = self.hit_cell(ray, i as usize, j as usize, t_interval)
+            {
+                return Some(hit);
+            }
+
+            if next_x < next_z {
+                i += step_i;
+                t = next_x;
+                next_x += delta_x;
+            } else {
+                j += step_j;
+                t = next_z;
+                next_z += delta_z;
+            }
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let far_corner = Vec3::new(
+            (self.columns - 1) as f64 * self.spacing.x,
+            self.max_height,
+            (self.rows - 1) as f64 * self.spacing.z,
+        );
+        let near_corner = Vec3::new(0., self.min_height, 0.);
+        Some(Aabb::new(self.corner + near_corner, self.corner + far_corner).padded())
+    }
+}
+
+// Möller–Trumbore; returns t and the barycentric weights of b and c
+fn intersect_triangle(ray: &Ray, a: Point3, b: Point3, c: Point3) -> Option<(f64, f64, f64)> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = Vec3::cross(&ray.direction, &edge2);
+    let determinant = Vec3::dot(&edge1, &p);
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+    let inverse = 1. / determinant;
+    let s = ray.origin - a;
+    let beta = Vec3::dot(&s, &p) * inverse;
+    if !(0. ..=1.).contains(&beta) {
+        return None;
+    }
+    let q = Vec3::cross(&s, &edge1);
+    let gamma = Vec3::dot(&ray.direction, &q) * inverse;
+    if gamma < 0. || beta + gamma > 1. {
+        return None;
+    }
+    Some((Vec3::dot(&edge2, &q) * inverse, beta, gamma))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Returns the width, height and samples normalized to [0, 1]
+fn parse_pgm(data: &[u8]) -> io::Result<(usize, usize, Vec<f64>)> {
+    let mut position = 0;
+    let next_token = |position: &mut usize| -> io::Result<String> {
+        // Skip whitespace and comments
+        loop {
+            match data.get(*position) {
+                Some(b'#') => {
+                    while data.get(*position).is_some_and(|&byte| byte != b'\n') {
+                        *position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => *position += 1,
+                Some(_) => break,
+                None => return Err(invalid_data("unexpected end of PGM header")),
+            }
+        }
+        let start = *position;
+        while data.get(*position).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+            *position += 1;
+        }
+        Ok(String::from_utf8_lossy(&data[start..*position]).into_owned())
+    };
+    let next_number = |position: &mut usize| -> io::Result<usize> {
+        next_token(position)?.parse().map_err(|_| invalid_data("malformed number in PGM"))
+    };
+
+    let magic = String::from_utf8_lossy(data.get(0..2).unwrap_or_default()).into_owned();
+    position += 2;
+    let width = next_number(&mut position)?;
+    let height = next_number(&mut position)?;
+    let max_value = next_number(&mut position)?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(invalid_data("PGM max value must be between 1 and 65535"));
+    }
+    let count = width.checked_mul(height).ok_or_else(|| invalid_data("PGM dimensions are too large"))?;
+
+    let samples: Vec<usize> = match magic.as_str() {
+        "P5" => {
+            // Exactly one whitespace byte separates the header from the raster
+            let raster = data.get(position + 1..).unwrap_or_default();
+            let bytes_per_sample = if max_value < 256 { 1 } else { 2 };
+            if count.checked_mul(bytes_per_sample).is_none_or(|size| raster.len() < size) {
+                return Err(invalid_data("PGM raster is truncated"));
+            }
+            raster
+                .chunks_exact(bytes_per_sample)
+                .take(count)
+                .map(|chunk| chunk.iter().fold(0, |value, &byte| value << 8 | byte as usize))
+                .collect()
+        }
+        "P2" => (0..count).map(|_| next_number(&mut position)).collect::<io::Result<_>>()?,
+        _ => return Err(invalid_data("not a PGM file")),
+    };
+    let scale = 1. / max_value as f64;
+    Ok((width, height, samples.into_iter().map(|sample| sample as f64 * scale).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::heightfield::Heightfield;
+    use crate::vec3::Vec3;
+
+    // 3x3 samples with heights rising by 1 per column, so the surface is the plane y = x
+    fn ramp() -> Heightfield {
+        let heights = vec![0., 1., 2., 0., 1., 2., 0., 1., 2.];
+        Heightfield::new(Vec3::zero(), Vec3::new(1., 1., 1.), 3, heights, Arc::new(Lambertian::new(Color::white())))
+    }
+
+    #[test]
+    fn hits_the_ramp_with_its_normal() {
+        let ray = Ray::new(Vec3::new(1.5, 5., 0.5), Vec3::new(0., -1., 0.));
+        let hit = ramp().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 3.5).abs() < 1e-9);
+        let expected = Vec3::new(-1., 1., 0.).normalize();
+        assert!((hit.normal - expected).length() < 1e-9);
+        assert!((hit.u - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn geometric_normal_is_the_triangles_own() {
+        // A ridge along the middle column: the interpolated normal at x = 0.5 leans less than the
+        // slope it sits on
+        let heights = vec![0., 1., 0., 0., 1., 0., 0., 1., 0.];
+        let ridge = Heightfield::new(Vec3::zero(), Vec3::new(1., 1., 1.), 3, heights, Arc::new(Lambertian::new(Color::white())));
+        let ray = Ray::new(Vec3::new(0.5, 5., 0.5), Vec3::new(0., -1., 0.));
+        let hit = ridge.hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+
+        assert!((hit.normal - Vec3::new(-1., 1., 0.).normalize()).length() < 1e-9);
+        assert!(hit.front_face);
+        assert!(hit.shading_normal.x < 0. && hit.shading_normal.x > hit.normal.x);
+        assert!((hit.dpdu - Vec3::new(2., 2., 0.)).length() < 1e-9);
+        assert!((hit.dpdv - Vec3::new(0., 0., 2.)).length() < 1e-9);
+    }
+
+    #[test]
+    fn traverses_cells_along_a_shallow_ray() {
+        // Skims over the low end of the ramp and runs into it further along
+        let ray = Ray::new(Vec3::new(-1., 1.25, 1.7), Vec3::new(1., 0., 0.));
+        let hit = ramp().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.location.x - 1.25).abs() < 1e-9);
+
+        let above = Ray::new(Vec3::new(-1., 2.5, 1.7), Vec3::new(1., 0., 0.));
+        assert!(ramp().hit(&above, Interval::new(0.001, f64::INFINITY)).is_none());
+    }
+
+    #[test]
+    fn loads_16_bit_pgm() {
+        let mut pgm = b"P5\n# terrain\n2 2\n65535\n".to_vec();
+        pgm.extend_from_slice(&[0x00, 0x00, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+        let material = Arc::new(Lambertian::new(Color::white()));
+        let heightfield = Heightfield::from_pgm(&mut pgm.as_slice(), Vec3::zero(), Vec3::new(1., 10., 1.), material).unwrap();
+
+        assert_eq!((heightfield.columns(), heightfield.rows()), (2, 2));
+        assert_eq!(heightfield.height(1, 0), 10.);
+        assert!((heightfield.height(0, 1) - 5.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn rejects_pgm_dimensions_that_overflow() {
+        let material = Arc::new(Lambertian::new(Color::white()));
+        let huge = format!("P5 {} {} 255\n", usize::MAX / 2, 3);
+        let error = Heightfield::from_pgm(&mut huge.as_bytes(), Vec3::zero(), Vec3::new(1., 1., 1.), material).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
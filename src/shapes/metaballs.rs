@@ -0,0 +1,181 @@
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::polynomial::{solve_in_range, solve_quadratic};
+use crate::ray::Ray;
+use crate::sphere::projected_sphere_uv;
+use crate::vec3::{Point3, Vec3};
+
+// Point source of the metaball field. Its influence `weight * (1 - d^2 / radius^2)^3` falls to zero
+// at `radius`; a negative weight carves into neighbouring balls.
+#[derive(Copy, Clone, Debug)]
+pub struct Metaball {
+    pub center: Point3,
+    pub radius: f64,
+    pub weight: f64,
+}
+
+// Isosurface where the summed field of the balls equals `threshold`. Along a ray each ball contributes
+// a degree 6 polynomial in t, so between the points where balls' influence starts or ends the field is
+// one polynomial whose roots can be isolated exactly instead of ray marched. UVs are projected onto the
+// surface from the balls' average center.
+#[derive(Clone)]
+pub struct Metaballs {
+    pub balls: Vec<Metaball>,
+    pub threshold: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Metaballs {
+    pub fn new(balls: Vec<Metaball>, threshold: f64, material: Arc<dyn Material>) -> Metaballs {
+        Metaballs { balls, threshold, material }
+    }
+
+    // Average of the ball centers, which UVs are projected from
+    pub fn center(&self) -> Point3 {
+        self.balls.iter().map(|ball| ball.center).sum::<Point3>() / self.balls.len().max(1) as f64
+    }
+
+    pub fn field(&self, point: Point3) -> f64 {
+        self.balls
+            .iter()
+            .map(|ball| {
+                let falloff = 1. - (point - ball.center).length_squared() / (ball.radius * ball.radius);
+                if falloff > 0. { ball.weight * falloff * falloff * falloff } else { 0. }
+            })
+            .sum()
+    }
+
+    pub fn gradient(&self, point: Point3) -> Vec3 {
+        self.balls
+            .iter()
+            .map(|ball| {
+                let r2 = ball.radius * ball.radius;
+                let offset = point - ball.center;
+                let falloff = 1. - offset.length_squared() / r2;
+                if falloff > 0. {
+                    ball.weight * 3. * falloff * falloff * (-2. / r2) * offset
+                } else {
+                    Vec3::zero()
+                }
+            })
+            .sum()
+    }
+
+    // Coefficients (highest power first) of the ball's influence along the ray, and the range of t
+    // where it applies
+    fn along_ray(ball: &Metaball, ray: &Ray) -> Option<([f64; 7], Interval)> {
+        let r2 = ball.radius * ball.radius;
+        let oc = ray.origin - ball.center;
+        let a = ray.direction.length_squared() / r2;
+        let b = 2. * Vec3::dot(&oc, &ray.direction) / r2;
+        let c = oc.length_squared() / r2;
+
+        let support = solve_quadratic(a, b, c - 1.);
+        let [enter, exit] = support[..] else {
+            return None;
+        };
+
+        // falloff(t) = s2 t^2 + s1 t + s0, cubed
+        let (s2, s1, s0) = (-a, -b, 1. - c);
+        let squared = [s2 * s2, 2. * s2 * s1, s1 * s1 + 2. * s2 * s0, 2. * s1 * s0, s0 * s0];
+        let mut cubed = [0.; 7];
+        for (i, square) in squared.iter().enumerate() {
+            cubed[i] += square * s2;
+            cubed[i + 1] += square * s1;
+            cubed[i + 2] += square * s0;
+        }
+        Some((cubed.map(|coefficient| coefficient * ball.weight), Interval::new(enter, exit)))
+    }
+}
+
+impl Hittable for Metaballs {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        let influences: Vec<([f64; 7], Interval)> =
+            self.balls.iter().filter_map(|ball| Metaballs::along_ray(ball, ray)).collect();
+
+        let mut breakpoints: Vec<f64> = influences
+            .iter()
+            .flat_map(|(_, support)| [support.min, support.max])
+            .map(|t| t.clamp(t_interval.min, t_interval.max))
+            .collect();
+        breakpoints.sort_by(f64::total_cmp);
+
+        for segment in breakpoints.windows(2) {
+            let (start, end) = (segment[0], segment[1]);
+            if end <= start {
+                continue;
+            }
+            let middle = 0.5 * (start + end);
+            let mut field = [0.; 7];
+            for (coefficients, support) in &influences {
+                if support.contains(middle) {
+                    for (total, coefficient) in field.iter_mut().zip(coefficients) {
+                        *total += coefficient;
+                    }
+                }
+            }
+            field[6] -= self.threshold;
+
+            let Some(t) = solve_in_range(&field, start, end)
+                .into_iter()
+                .find(|&t| t_interval.surrounds(t))
+            else {
+                continue;
+            };
+            let location = ray.at(t);
+            let normal = (-self.gradient(location)).normalize();
+            let ((u, v), (dpdu, dpdv)) = projected_sphere_uv(self.center(), location, normal);
+            return Some(Hit::new(ray, t, normal, u, v, self.material.clone()).with_tangents(dpdu, dpdv));
+        }
+        None
+    }
+
+    // The surface can't leave the region some ball influences
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.balls
+            .iter()
+            .map(|ball| Aabb::around_sphere(ball.center, ball.radius))
+            .reduce(|a, b| a.union(&b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::metaballs::{Metaball, Metaballs};
+    use crate::vec3::Vec3;
+
+    fn metaballs(centers: &[Vec3]) -> Metaballs {
+        let balls = centers.iter().map(|&center| Metaball { center, radius: 1., weight: 1. }).collect();
+        Metaballs::new(balls, 0.125, Arc::new(Lambertian::new(Color::white())))
+    }
+
+    #[test]
+    fn single_ball_is_a_sphere() {
+        // (1 - r^2)^3 = 1/8 at r^2 = 1/2
+        let ray = Ray::new(Vec3::new(0., 0., 5.), Vec3::new(0., 0., -1.));
+        let hit = metaballs(&[Vec3::zero()]).hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - (5. - 0.5f64.sqrt())).abs() < 1e-9);
+        assert!((hit.normal - Vec3::new(0., 0., 1.)).length() < 1e-9);
+    }
+
+    #[test]
+    fn nearby_balls_merge() {
+        // Halfway between two balls 1.5 apart each one alone is below the threshold, together they're above
+        let ray = Ray::new(Vec3::new(0., 5., 0.), Vec3::new(0., -1., 0.));
+        assert!(metaballs(&[Vec3::new(-0.75, 0., 0.)]).hit(&ray, Interval::new(0.001, f64::INFINITY)).is_none());
+
+        let merged = metaballs(&[Vec3::new(-0.75, 0., 0.), Vec3::new(0.75, 0., 0.)]);
+        let hit = merged.hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!(hit.location.y > 0.);
+        assert!((merged.field(hit.location) - 0.125).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,12 @@
+pub mod axis_aligned_box;
+pub(crate) mod axis_frame;
+pub mod capsule;
+pub mod cone;
+pub mod cylinder;
+pub mod disk;
+pub mod heightfield;
+pub mod metaballs;
+pub mod plane;
+pub mod quad;
+pub mod sdf_hittable;
+pub mod torus;
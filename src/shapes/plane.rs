@@ -0,0 +1,110 @@
+use std::sync::Arc;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// Infinite plane through `point`. UVs are unbounded world-space distances along the plane,
+// so textures are expected to repeat.
+#[derive(Clone)]
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    pub material: Arc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: Arc<dyn Material>) -> Plane {
+        let normal = normal.normalize();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        Plane { point, normal, tangent, bitangent, material }
+    }
+
+    pub fn point(&self) -> Point3 {
+        self.point
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        let denominator = Vec3::dot(&self.normal, &ray.direction);
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = Vec3::dot(&self.normal, &(self.point - ray.origin)) / denominator;
+        if !t_interval.surrounds(t) {
+            return None;
+        }
+
+        let location = ray.at(t);
+        let planar = location - self.point;
+        let hit = Hit::new(
+            ray,
+            t,
+            self.normal,
+            Vec3::dot(&planar, &self.tangent),
+            Vec3::dot(&planar, &self.bitangent),
+            self.material.clone(),
+        );
+        Some(hit.with_tangents(self.tangent, self.bitangent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::plane::Plane;
+    use crate::vec3::{Point3, Vec3};
+
+    fn ground() -> Plane {
+        Plane::new(Point3::new(0., -1., 0.), Vec3::new(0., 2., 0.), Arc::new(Lambertian::new(Color::white())))
+    }
+
+    #[test]
+    fn hit_reports_distance_and_planar_uv() {
+        let ray = Ray::new(Point3::new(3., 1., -2.), Vec3::new(0., -1., 0.));
+        let hit = ground().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+
+        assert_eq!(hit.t, 2.);
+        assert_eq!(hit.location, Point3::new(3., -1., -2.));
+        // UVs are distances along the plane, so they grow as far as the hit is from `point`
+        assert!((hit.u * hit.u + hit.v * hit.v - 13.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn misses_parallel_rays_and_rays_pointing_away() {
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let parallel = Ray::new(Point3::new(0., 1., 0.), Vec3::new(1., 0., 0.));
+        let away = Ray::new(Point3::new(0., 1., 0.), Vec3::new(0., 1., 0.));
+
+        assert!(ground().hit(&parallel, interval).is_none());
+        assert!(ground().hit(&away, interval).is_none());
+    }
+
+    #[test]
+    fn normal_points_along_the_plane_normal_from_either_side() {
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let from_above = Ray::new(Point3::new(0., 1., 0.), Vec3::new(0., -1., 0.));
+        let from_below = Ray::new(Point3::new(0., -3., 0.), Vec3::new(0., 1., 0.));
+
+        let above = ground().hit(&from_above, interval).unwrap();
+        let below = ground().hit(&from_below, interval).unwrap();
+        assert_eq!(above.normal, Vec3::new(0., 1., 0.));
+        assert_eq!(below.normal, Vec3::new(0., 1., 0.));
+        assert!(above.front_face && !below.front_face);
+        assert_eq!(below.oriented_normal(), Vec3::new(0., -1., 0.));
+    }
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// Parallelogram spanned by the edges `u` and `v` from the `corner`.
+// The normal is u x v, so the winding of the edges decides which side is the front.
+#[derive(Clone)]
+pub struct Quad {
+    corner: Point3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    d: f64,
+    w: Vec3,
+    pub material: Arc<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(corner: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Quad {
+        let n = Vec3::cross(&u, &v);
+        let normal = n.normalize();
+        Quad {
+            corner,
+            u,
+            v,
+            normal,
+            d: Vec3::dot(&normal, &corner),
+            w: n / n.length_squared(),
+            material,
+        }
+    }
+
+    pub fn corner(&self) -> Point3 {
+        self.corner
+    }
+
+    pub fn u(&self) -> Vec3 {
+        self.u
+    }
+
+    pub fn v(&self) -> Vec3 {
+        self.v
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        let denominator = Vec3::dot(&self.normal, &ray.direction);
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - Vec3::dot(&self.normal, &ray.origin)) / denominator;
+        if !t_interval.surrounds(t) {
+            return None;
+        }
+
+        // Coordinates of the hit in the basis of the edges; inside the quad both are in [0, 1]
+        let location = ray.at(t);
+        let planar = location - self.corner;
+        let alpha = Vec3::dot(&self.w, &Vec3::cross(&planar, &self.v));
+        let beta = Vec3::dot(&self.w, &Vec3::cross(&self.u, &planar));
+        let unit = Interval::new(0., 1.);
+        if !unit.contains(alpha) || !unit.contains(beta) {
+            return None;
+        }
+
+        Some(Hit::new(ray, t, self.normal, alpha, beta, self.material.clone()).with_tangents(self.u, self.v))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let corners = [self.corner, self.corner + self.u, self.corner + self.v, self.corner + self.u + self.v];
+        Some(Aabb::around_points(&corners).padded())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::quad::Quad;
+    use crate::vec3::Vec3;
+
+    fn unit_quad() -> Quad {
+        Quad::new(
+            Vec3::new(0., 0., -1.),
+            Vec3::new(2., 0., 0.),
+            Vec3::new(0., 4., 0.),
+            Arc::new(Lambertian::new(Color::white())),
+        )
+    }
+
+    #[test]
+    fn hit_reports_edge_coordinates_as_uv() {
+        let ray = Ray::new(Vec3::new(0.5, 1., 0.), Vec3::new(0., 0., -1.));
+        let hit = unit_quad().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+
+        assert_eq!(hit.t, 1.);
+        assert_eq!(hit.normal, Vec3::new(0., 0., 1.));
+        assert_eq!((hit.u, hit.v), (0.25, 0.25));
+    }
+
+    #[test]
+    fn misses_outside_edges_and_parallel_rays() {
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let outside = Ray::new(Vec3::new(2.5, 1., 0.), Vec3::new(0., 0., -1.));
+        let parallel = Ray::new(Vec3::new(0.5, 1., 0.), Vec3::new(1., 0., 0.));
+
+        assert!(unit_quad().hit(&outside, interval).is_none());
+        assert!(unit_quad().hit(&parallel, interval).is_none());
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sdf::Sdf;
+use crate::sphere::projected_sphere_uv;
+use crate::vec3::{Point3, Vec3};
+
+// Surface of a signed distance field, found by sphere tracing.
+// There is no natural parameterization, so UVs are projected onto the surface from the middle of
+// `bounds`, or from the origin without them.
+#[derive(Clone)]
+pub struct SdfHittable {
+    pub sdf: Arc<dyn Sdf>,
+    pub material: Arc<dyn Material>,
+    pub max_steps: u32,
+    pub epsilon: f64,         // Distance at which we consider the surface reached
+    pub step_scale: f64,      // Fraction of the distance to step; below 1 for fields that overestimate
+    pub bounds: Option<Aabb>, // Optional box the surface is known to lie in; marching is limited to it
+}
+
+impl SdfHittable {
+    pub fn new(sdf: Arc<dyn Sdf>, material: Arc<dyn Material>) -> SdfHittable {
+        SdfHittable { sdf, material, max_steps: 256, epsilon: 1e-5, step_scale: 1., bounds: None }
+    }
+
+    pub fn normal(&self, point: Point3) -> Vec3 {
+        let h = self.epsilon;
+        let dx = Vec3::new(h, 0., 0.);
+        let dy = Vec3::new(0., h, 0.);
+        let dz = Vec3::new(0., 0., h);
+        Vec3::new(
+            self.sdf.distance(point + dx) - self.sdf.distance(point - dx),
+            self.sdf.distance(point + dy) - self.sdf.distance(point - dy),
+            self.sdf.distance(point + dz) - self.sdf.distance(point - dz),
+        )
+        .normalize()
+    }
+}
+
+impl Hittable for SdfHittable {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        let t_interval = match self.bounds {
+            Some(bounds) => bounds.clip(ray, t_interval)?,
+            None => t_interval,
+        };
+        let direction_length = ray.direction.length();
+        let mut t = t_interval.min;
+        // Rays that start inside (e.g. refracted ones) march towards the surface from the other side
+        let side = self.sdf.distance(ray.at(t)).signum();
+        // A ray that starts at the surface, as one leaving it does, has to get away from it before
+        // the next surface counts
+        let mut leaving = true;
+
+        for _ in 0..self.max_steps {
+            if !t_interval.contains(t) {
+                return None;
+            }
+            let location = ray.at(t);
+            let distance = side * self.sdf.distance(location);
+            if distance >= self.epsilon {
+                leaving = false;
+            } else if !leaving && t_interval.surrounds(t) {
+                let normal = self.normal(location);
+                let center = self.bounds.map_or(Point3::zero(), |bounds| 0.5 * (bounds.min + bounds.max));
+                let ((u, v), (dpdu, dpdv)) = projected_sphere_uv(center, location, normal);
+                return Some(Hit::new(ray, t, normal, u, v, self.material.clone()).with_tangents(dpdu, dpdv));
+            }
+            t += (self.step_scale * distance).max(self.epsilon) / direction_length;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::sdf::{sphere, union};
+    use crate::shapes::sdf_hittable::SdfHittable;
+    use crate::vec3::Vec3;
+
+    fn sdf_sphere() -> SdfHittable {
+        SdfHittable::new(Arc::new(sphere(Vec3::new(0., 0., -3.), 1.)), Arc::new(Lambertian::new(Color::white())))
+    }
+
+    #[test]
+    fn matches_analytic_sphere() {
+        let ray = Ray::new(Vec3::zero(), Vec3::new(0., 0., -2.));
+        let hit = sdf_sphere().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 1.).abs() < 1e-4);
+        assert!((hit.normal - Vec3::new(0., 0., 1.)).length() < 1e-4);
+    }
+
+    #[test]
+    fn ray_from_inside_finds_the_far_wall() {
+        let ray = Ray::new(Vec3::new(0., 0., -3.), Vec3::new(0., 1., 0.));
+        let hit = sdf_sphere().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.location.y - 1.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn grazing_ray_leaving_the_surface_finds_the_next_one() {
+        let spheres = union(sphere(Vec3::zero(), 1.), sphere(Vec3::new(4., 1., 0.), 1.));
+        let mut hittable = SdfHittable::new(Arc::new(spheres), Arc::new(Lambertian::new(Color::white())));
+        // Creeping along the first sphere takes many small steps
+        hittable.max_steps = 5000;
+        let ray = Ray::new(Vec3::new(0., 1., 0.), Vec3::new(1., 0., 0.));
+        let hit = hittable.hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 3.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn miss_stops_at_interval_end() {
+        let ray = Ray::new(Vec3::zero(), Vec3::new(0., 0., 1.));
+        assert!(sdf_sphere().hit(&ray, Interval::new(0.001, 100.)).is_none());
+    }
+}
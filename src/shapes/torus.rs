@@ -0,0 +1,142 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::polynomial::solve_quartic;
+use crate::ray::Ray;
+use crate::shapes::axis_frame::{azimuth_direction, azimuth_tangent, azimuth_uv, AxisFrame};
+use crate::vec3::{Point3, Vec3};
+
+// Ring around `axis` through `center`. `major_radius` is the distance from the center to the middle
+// of the tube, `minor_radius` the radius of the tube. u goes around the ring, v around the tube.
+#[derive(Clone)]
+pub struct Torus {
+    frame: AxisFrame,
+    major_radius: f64,
+    minor_radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl Torus {
+    pub fn new(center: Point3, axis: Vec3, major_radius: f64, minor_radius: f64, material: Arc<dyn Material>) -> Torus {
+        assert!(major_radius > 0. && minor_radius > 0., "a torus needs positive radii");
+        Torus { frame: AxisFrame::new(center, axis), major_radius, minor_radius, material }
+    }
+
+    pub fn center(&self) -> Point3 {
+        self.frame.origin
+    }
+
+    pub fn major_radius(&self) -> f64 {
+        self.major_radius
+    }
+
+    pub fn minor_radius(&self) -> f64 {
+        self.minor_radius
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        let local = self.frame.local_ray(ray);
+        // Solving with a unit direction keeps the quartic's coefficients well scaled
+        let direction_length = local.direction.length();
+        let o = local.origin;
+        let d = local.direction / direction_length;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2) along p = o + s d
+        let big_r2 = self.major_radius * self.major_radius;
+        let e = o.length_squared() + big_r2 - self.minor_radius * self.minor_radius;
+        let f = Vec3::dot(&o, &d);
+        let roots = solve_quartic(
+            1.,
+            4. * f,
+            4. * f * f + 2. * e - 4. * big_r2 * (d.x * d.x + d.z * d.z),
+            4. * f * e - 8. * big_r2 * (o.x * d.x + o.z * d.z),
+            e * e - 4. * big_r2 * (o.x * o.x + o.z * o.z),
+        );
+        let t = roots
+            .into_iter()
+            .map(|s| s / direction_length)
+            .find(|&t| t_interval.surrounds(t))?;
+
+        let p = local.at(t);
+        let ring_distance = (p.x * p.x + p.z * p.z).sqrt();
+        let tube_center = if ring_distance < 1e-12 {
+            Vec3::zero()
+        } else {
+            Vec3::new(p.x, 0., p.z) * (self.major_radius / ring_distance)
+        };
+        let normal = (p - tube_center).normalize();
+        let u = azimuth_uv(p);
+        // v turns the point around the tube's circle, in the plane through the axis
+        let dpdv = 2. * PI * (Vec3::new(0., ring_distance - self.major_radius, 0.) - p.y * azimuth_direction(u));
+        let hit = Hit::new(
+            ray,
+            t,
+            self.frame.world_vector(normal),
+            u,
+            (f64::atan2(p.y, ring_distance - self.major_radius) + PI) / (2. * PI),
+            self.material.clone(),
+        );
+        Some(hit.with_tangents(self.frame.world_vector(azimuth_tangent(p)), self.frame.world_vector(dpdv)))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::around_sphere(self.center(), self.major_radius + self.minor_radius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::shapes::torus::Torus;
+    use crate::vec3::Vec3;
+
+    fn torus() -> Torus {
+        Torus::new(Vec3::zero(), Vec3::new(0., 1., 0.), 2., 0.5, Arc::new(Lambertian::new(Color::white())))
+    }
+
+    #[test]
+    fn ray_through_the_hole_misses() {
+        let ray = Ray::new(Vec3::new(0., 5., 0.), Vec3::new(0., -1., 0.));
+        assert!(torus().hit(&ray, Interval::new(0.001, f64::INFINITY)).is_none());
+    }
+
+    #[test]
+    fn ray_across_the_ring_hits_the_outer_edge() {
+        let ray = Ray::new(Vec3::new(10., 0., 0.), Vec3::new(-2., 0., 0.));
+        let hit = torus().hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 3.75).abs() < 1e-9);
+        assert!((hit.normal - Vec3::new(1., 0., 0.)).length() < 1e-9);
+    }
+
+    #[test]
+    fn grazing_the_top_of_the_tube() {
+        let grazing = |y: f64| Ray::new(Vec3::new(10., y, 0.), Vec3::new(-1., 0., 0.));
+        assert!(torus().hit(&grazing(0.5 + 1e-7), Interval::new(0.001, f64::INFINITY)).is_none());
+
+        let hit = torus().hit(&grazing(0.5 - 1e-7), Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.location.x - 2.).abs() < 1e-2);
+        assert!(hit.normal.y > 0.99);
+    }
+
+    #[test]
+    #[should_panic(expected = "a torus needs positive radii")]
+    fn rejects_a_torus_without_a_ring() {
+        Torus::new(Vec3::zero(), Vec3::new(0., 1., 0.), 0., 0.5, Arc::new(Lambertian::new(Color::white())));
+    }
+
+    #[test]
+    #[should_panic(expected = "a torus needs positive radii")]
+    fn rejects_a_torus_without_a_tube() {
+        Torus::new(Vec3::zero(), Vec3::new(0., 1., 0.), 2., 0., Arc::new(Lambertian::new(Color::white())));
+    }
+}
@@ -0,0 +1,30 @@
+use crate::hittable::{Hit, Hittable};
+use crate::ray::Ray;
+
+// Stretch of a ray that lies inside a solid, from the surface it enters through
+// to the surface it leaves through. Both normals point out of the solid.
+#[derive(Clone)]
+pub struct Span {
+    pub enter: Hit,
+    pub exit: Hit,
+}
+
+impl Span {
+    // The span of a convex shape from all of its surface crossings along a ray.
+    // A single crossing is a graze and doesn't enclose any volume.
+    pub fn convex(mut hits: Vec<Hit>) -> Vec<Span> {
+        if hits.len() < 2 {
+            return vec![];
+        }
+        hits.sort_by(|a, b| a.t.total_cmp(&b.t));
+        let exit = hits.pop().unwrap();
+        let enter = hits.swap_remove(0);
+        vec![Span { enter, exit }]
+    }
+}
+
+// A closed shape with a well-defined inside, which is what constructive solid geometry needs.
+// `spans` covers the whole line of the ray, including negative t, sorted and non-overlapping.
+pub trait Solid: Hittable {
+    fn spans(&self, ray: &Ray) -> Vec<Span>;
+}
@@ -0,0 +1,285 @@
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign};
+use std::sync::LazyLock;
+use crate::color::Color;
+use crate::vec3::Vec3;
+
+// Range of wavelengths (in nm) the spectral mode samples, covering everything the eye sees
+pub const WAVELENGTH_MIN: f64 = 360.;
+pub const WAVELENGTH_MAX: f64 = 830.;
+
+// Number of wavelengths carried along each path
+pub const WAVELENGTH_SAMPLES: usize = 4;
+
+// Wavelengths one path is traced at. With hero wavelength sampling the first ("hero") one is
+// random and the rest are spread evenly from it across the range, which keeps the path cheap
+// while the colour noise averages out much faster than with a single wavelength.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SampledWavelengths {
+    pub lambda: [f64; WAVELENGTH_SAMPLES],
+    pub pdf: [f64; WAVELENGTH_SAMPLES],
+}
+
+impl SampledWavelengths {
+    // `u` in [0, 1) picks the hero wavelength
+    pub fn sample_uniform(u: f64) -> SampledWavelengths {
+        let range = WAVELENGTH_MAX - WAVELENGTH_MIN;
+        let lambda = std::array::from_fn(|i| {
+            let offset = (u + i as f64 / WAVELENGTH_SAMPLES as f64).fract();
+            WAVELENGTH_MIN + offset * range
+        });
+        SampledWavelengths { lambda, pdf: [1. / range; WAVELENGTH_SAMPLES] }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    // Drops every wavelength but the hero, for when a path splits by wavelength (like light
+    // refracting through a prism) and the others can't follow the direction the hero took
+    pub fn terminate_secondary(&mut self) {
+        if self.secondary_terminated() {
+            return;
+        }
+        for pdf in &mut self.pdf[1..] {
+            *pdf = 0.;
+        }
+        self.pdf[0] /= WAVELENGTH_SAMPLES as f64;
+    }
+
+    pub fn secondary_terminated(&self) -> bool {
+        self.pdf[1..].iter().all(|&pdf| pdf == 0.)
+    }
+}
+
+// Values of a spectral quantity (radiance, reflectance, ...) at each of the `SampledWavelengths`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SampledSpectrum(pub [f64; WAVELENGTH_SAMPLES]);
+
+impl SampledSpectrum {
+    pub fn constant(value: f64) -> SampledSpectrum {
+        SampledSpectrum([value; WAVELENGTH_SAMPLES])
+    }
+
+    pub fn from_fn(wavelengths: &SampledWavelengths, function: impl Fn(f64) -> f64) -> SampledSpectrum {
+        SampledSpectrum(wavelengths.lambda.map(function))
+    }
+
+    // Smooth spectrum that maps back to `color`; see `rgb_to_spectrum`
+    pub fn from_rgb(color: Color, wavelengths: &SampledWavelengths) -> SampledSpectrum {
+        SampledSpectrum::from_fn(wavelengths, |lambda| rgb_to_spectrum(color, lambda))
+    }
+
+    pub fn average(&self) -> f64 {
+        self.0.iter().sum::<f64>() / WAVELENGTH_SAMPLES as f64
+    }
+
+    // Monte Carlo estimate of the CIE XYZ color of the spectrum, normalized so Y is luminance
+    pub fn to_xyz(&self, wavelengths: &SampledWavelengths) -> Vec3 {
+        let sum: Vec3 = (0..WAVELENGTH_SAMPLES)
+            .filter(|&i| wavelengths.pdf[i] > 0.)
+            .map(|i| self.0[i] / wavelengths.pdf[i] * cie_xyz(wavelengths.lambda[i]))
+            .sum();
+        sum / (WAVELENGTH_SAMPLES as f64 * TABLES.y_integral)
+    }
+
+    // Linear sRGB, white balanced so that a flat spectrum comes out neutral
+    pub fn to_rgb(&self, wavelengths: &SampledWavelengths) -> Color {
+        xyz_to_linear_srgb(self.to_xyz(wavelengths)) * TABLES.white_balance
+    }
+}
+
+impl Add for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn add(self, rhs: SampledSpectrum) -> SampledSpectrum {
+        SampledSpectrum(std::array::from_fn(|i| self.0[i] + rhs.0[i]))
+    }
+}
+
+impl AddAssign for SampledSpectrum {
+    fn add_assign(&mut self, rhs: SampledSpectrum) {
+        *self = *self + rhs;
+    }
+}
+
+impl Mul for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn mul(self, rhs: SampledSpectrum) -> SampledSpectrum {
+        SampledSpectrum(std::array::from_fn(|i| self.0[i] * rhs.0[i]))
+    }
+}
+
+impl MulAssign for SampledSpectrum {
+    fn mul_assign(&mut self, rhs: SampledSpectrum) {
+        *self = *self * rhs;
+    }
+}
+
+impl Mul<f64> for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn mul(self, rhs: f64) -> SampledSpectrum {
+        SampledSpectrum(self.0.map(|value| value * rhs))
+    }
+}
+
+impl Div<f64> for SampledSpectrum {
+    type Output = SampledSpectrum;
+
+    fn div(self, rhs: f64) -> SampledSpectrum {
+        SampledSpectrum(self.0.map(|value| value / rhs))
+    }
+}
+
+// CIE 1931 2° color matching functions, using the multi-lobe Gaussian fit from
+// Wyman, Sloan and Shirley, "Simple Analytic Approximations to the CIE XYZ Color Matching Functions" (2013)
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    let lobe = |mean: f64, sigma_below: f64, sigma_above: f64| {
+        let sigma = if lambda < mean { sigma_below } else { sigma_above };
+        let t = (lambda - mean) / sigma;
+        (-0.5 * t * t).exp()
+    };
+    Vec3::new(
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7) - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
+
+// XYZ to linear sRGB (D65 white point)
+pub fn xyz_to_linear_srgb(xyz: Vec3) -> Color {
+    Color::new(
+        3.2404542 * xyz.x - 1.5371385 * xyz.y - 0.4985314 * xyz.z,
+        -0.9692660 * xyz.x + 1.8760108 * xyz.y + 0.0415560 * xyz.z,
+        0.0556434 * xyz.x - 0.2040259 * xyz.y + 1.0572252 * xyz.z,
+    )
+}
+
+// Turns an RGB color into a smooth, non-negative spectrum. The spectrum is a mix of three broad
+// bands (blue, green and red, softly overlapping), with weights solved so that converting the
+// spectrum back gives the same color. Colors far outside sRGB would need negative weights and
+// come back somewhat desaturated because the spectrum is clamped at 0.
+pub fn rgb_to_spectrum(color: Color, lambda: f64) -> f64 {
+    let weights = TABLES.rgb_to_band_weights.transform(color);
+    Vec3::dot(&weights, &bands(lambda)).max(0.)
+}
+
+// Blue, green and red bands; they add up to 1 at every wavelength
+fn bands(lambda: f64) -> Vec3 {
+    let sigmoid = |x: f64| 1. / (1. + (-x).exp());
+    let blue = 1. - sigmoid((lambda - 490.) / 8.);
+    let red = sigmoid((lambda - 590.) / 8.);
+    Vec3::new(red, 1. - blue - red, blue)
+}
+
+// Numbers derived from the color matching functions once, by integrating over the visible range
+struct SpectralTables {
+    y_integral: f64,
+    white_balance: Color,
+    rgb_to_band_weights: Mat3,
+}
+
+static TABLES: LazyLock<SpectralTables> = LazyLock::new(|| {
+    let step = 1.;
+    let wavelengths = (0..((WAVELENGTH_MAX - WAVELENGTH_MIN) / step) as usize).map(|i| WAVELENGTH_MIN + (i as f64 + 0.5) * step);
+    let integrate = |weight: &dyn Fn(f64) -> f64| -> Vec3 { wavelengths.clone().map(|lambda| weight(lambda) * step * cie_xyz(lambda)).sum() };
+
+    let y_integral = integrate(&|_| 1.).y;
+    let rgb = |weight: &dyn Fn(f64) -> f64| xyz_to_linear_srgb(integrate(weight) / y_integral);
+
+    // A flat spectrum is the equal-energy white, which is slightly pink in sRGB
+    let white_balance = rgb(&|_| 1.).map(|channel| 1. / channel);
+    let band_colors = Mat3::from_columns(
+        rgb(&|lambda| bands(lambda).x) * white_balance,
+        rgb(&|lambda| bands(lambda).y) * white_balance,
+        rgb(&|lambda| bands(lambda).z) * white_balance,
+    );
+    SpectralTables { y_integral, white_balance, rgb_to_band_weights: band_colors.inverse() }
+});
+
+// Just enough of a 3x3 matrix for the band weights, stored as rows
+#[derive(Copy, Clone, Debug)]
+struct Mat3 {
+    rows: [Vec3; 3],
+}
+
+impl Mat3 {
+    fn from_columns(a: Vec3, b: Vec3, c: Vec3) -> Mat3 {
+        Mat3 { rows: [Vec3::new(a.x, b.x, c.x), Vec3::new(a.y, b.y, c.y), Vec3::new(a.z, b.z, c.z)] }
+    }
+
+    fn transform(&self, v: Vec3) -> Vec3 {
+        Vec3::new(Vec3::dot(&self.rows[0], &v), Vec3::dot(&self.rows[1], &v), Vec3::dot(&self.rows[2], &v))
+    }
+
+    // The inverse of a matrix with rows r0, r1, r2 has the columns r1 x r2, r2 x r0, r0 x r1 over the determinant
+    fn inverse(&self) -> Mat3 {
+        let [r0, r1, r2] = self.rows;
+        let determinant = Vec3::dot(&r0, &Vec3::cross(&r1, &r2));
+        Mat3::from_columns(
+            Vec3::cross(&r1, &r2) / determinant,
+            Vec3::cross(&r2, &r0) / determinant,
+            Vec3::cross(&r0, &r1) / determinant,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rng, Rng};
+    use crate::color::Color;
+    use crate::spectrum::{SampledSpectrum, SampledWavelengths, WAVELENGTH_MAX, WAVELENGTH_MIN};
+
+    fn monte_carlo_rgb(color: Color, samples: usize) -> Color {
+        let mut rng = rng();
+        let total: Color = (0..samples)
+            .map(|_| {
+                let wavelengths = SampledWavelengths::sample_uniform(rng.random());
+                SampledSpectrum::from_rgb(color, &wavelengths).to_rgb(&wavelengths)
+            })
+            .sum();
+        total / samples as f64
+    }
+
+    #[test]
+    fn hero_wavelengths_are_spread_evenly() {
+        let wavelengths = SampledWavelengths::sample_uniform(0.9);
+        let spacing = (WAVELENGTH_MAX - WAVELENGTH_MIN) / 4.;
+        assert!((wavelengths.lambda[1] - (wavelengths.lambda[0] + spacing - (WAVELENGTH_MAX - WAVELENGTH_MIN))).abs() < 1e-9);
+        assert!(wavelengths.lambda.iter().all(|lambda| (WAVELENGTH_MIN..WAVELENGTH_MAX).contains(lambda)));
+    }
+
+    #[test]
+    fn white_is_a_flat_spectrum() {
+        let wavelengths = SampledWavelengths::sample_uniform(0.3);
+        let white = SampledSpectrum::from_rgb(Color::white(), &wavelengths);
+        for value in white.0 {
+            assert!((value - 1.).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn colors_survive_the_round_trip() {
+        for color in [Color::white(), Color::new(0.8, 0.3, 0.2), Color::new(0.1, 0.2, 0.5), Color::new(0.5, 0.7, 1.)] {
+            let round_trip = monte_carlo_rgb(color, 40000);
+            assert!((round_trip - color).length() < 0.02, "{color:?} came back as {round_trip:?}");
+        }
+    }
+
+    #[test]
+    fn terminating_secondary_wavelengths_keeps_the_estimate_unbiased() {
+        let color = Color::new(0.2, 0.6, 0.9);
+        let samples = 40000;
+        // Stratified, since a single wavelength per sample is too noisy to test reliably at random
+        let total: Color = (0..samples)
+            .map(|i| {
+                let mut wavelengths = SampledWavelengths::sample_uniform((i as f64 + 0.5) / samples as f64);
+                wavelengths.terminate_secondary();
+                SampledSpectrum::from_rgb(color, &wavelengths).to_rgb(&wavelengths)
+            })
+            .sum();
+        let estimate = total / samples as f64;
+        assert!((estimate - color).length() < 0.03, "{estimate:?}");
+    }
+}
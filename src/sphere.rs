@@ -1,8 +1,11 @@
+use std::f64::consts::PI;
 use std::sync::Arc;
+use crate::aabb::Aabb;
 use crate::hittable::{Hit, Hittable};
-use crate::interval::Interval;
+use crate::interval::{motion_fraction, Interval};
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::solid::{Solid, Span};
 use crate::vec3::{Point3, Vec3};
 
 #[derive(Clone)]
@@ -12,29 +15,205 @@ pub struct Sphere {
     pub material: Arc<dyn Material>,
 }
 
+impl Sphere {
+    pub fn new(center: Point3, radius: f64, material: Arc<dyn Material>) -> Sphere {
+        Sphere { center, radius, material }
+    }
+}
+
 impl Hittable for Sphere {
     fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
-        let oc = self.center - ray.origin;
-        let a = ray.direction.length_squared();
-        let h = Vec3::dot(&ray.direction, &oc);
-        let c = oc.length_squared() - self.radius * self.radius;
-        let discriminant = h * h - a * c;
+        hit_sphere(self.center, self.radius, &self.material, ray, t_interval)
+    }
 
-        if discriminant < 0. {
-            return None;
-        }
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::around_sphere(self.center, self.radius))
+    }
+}
 
-        let mut root = (h - discriminant.sqrt()) / a;
+impl Solid for Sphere {
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        sphere_spans(self.center, self.radius, &self.material, ray)
+    }
+}
+
+// Sphere moving in a straight line from `start_center` to `end_center` while `ray.time` goes over
+// `motion_time`. Outside that interval it stays at whichever end is closest.
+#[derive(Clone)]
+pub struct MovingSphere {
+    pub start_center: Point3,
+    pub end_center: Point3,
+    pub motion_time: Interval,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+}
+
+impl MovingSphere {
+    pub fn new(start_center: Point3, end_center: Point3, motion_time: Interval, radius: f64, material: Arc<dyn Material>) -> MovingSphere {
+        MovingSphere { start_center, end_center, motion_time, radius, material }
+    }
+
+    pub fn center_at(&self, time: f64) -> Point3 {
+        let fraction = motion_fraction(self.motion_time, time);
+        self.start_center + fraction * (self.end_center - self.start_center)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        hit_sphere(self.center_at(ray.time), self.radius, &self.material, ray, t_interval)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let start = Aabb::around_sphere(self.start_center, self.radius);
+        let end = Aabb::around_sphere(self.end_center, self.radius);
+        Some(start.union(&end))
+    }
+}
+
+impl Solid for MovingSphere {
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        sphere_spans(self.center_at(ray.time), self.radius, &self.material, ray)
+    }
+}
+
+// Roots of the ray/sphere equation as (h, discriminant, a), with the roots being (h -+ sqrt(discriminant)) / a
+fn sphere_roots(center: Point3, radius: f64, ray: &Ray) -> (f64, f64, f64) {
+    let oc = center - ray.origin;
+    let a = ray.direction.length_squared();
+    let h = Vec3::dot(&ray.direction, &oc);
+    let c = oc.length_squared() - radius * radius;
+    (h, h * h - a * c, a)
+}
+
+fn hit_sphere(center: Point3, radius: f64, material: &Arc<dyn Material>, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+    let (h, discriminant, a) = sphere_roots(center, radius, ray);
+    if discriminant < 0. {
+        return None;
+    }
+
+    let mut root = (h - discriminant.sqrt()) / a;
+    if !t_interval.surrounds(root) {
+        root = (h + discriminant.sqrt()) / a;
         if !t_interval.surrounds(root) {
-            root = (h + discriminant.sqrt()) / a;
-            if !t_interval.surrounds(root) {
-                return None;
-            }
+            return None;
         }
+    }
+
+    Some(sphere_hit_at(center, radius, material, ray, root))
+}
+
+fn sphere_spans(center: Point3, radius: f64, material: &Arc<dyn Material>, ray: &Ray) -> Vec<Span> {
+    let (h, discriminant, a) = sphere_roots(center, radius, ray);
+    if discriminant <= 0. {
+        return vec![];
+    }
+
+    let enter = (h - discriminant.sqrt()) / a;
+    let exit = (h + discriminant.sqrt()) / a;
+    vec![Span {
+        enter: sphere_hit_at(center, radius, material, ray, enter),
+        exit: sphere_hit_at(center, radius, material, ray, exit),
+    }]
+}
+
+fn sphere_hit_at(center: Point3, radius: f64, material: &Arc<dyn Material>, ray: &Ray, t: f64) -> Hit {
+    let location = ray.at(t);
+    let normal = (location - center) / radius;
+    let (u, v) = sphere_uv(normal);
+    let (dpdu, dpdv) = sphere_tangents(radius, u, v);
+    Hit::new(ray, t, normal, u, v, material.clone()).with_tangents(dpdu, dpdv)
+}
+
+// Derivatives of the point at (u, v) under the `sphere_uv` mapping, which is
+// (-sin(theta) cos(phi), -cos(theta), sin(theta) sin(phi)) with phi = 2 pi u and theta = pi v
+fn sphere_tangents(radius: f64, u: f64, v: f64) -> (Vec3, Vec3) {
+    let (sin_phi, cos_phi) = (2. * PI * u).sin_cos();
+    let (sin_theta, cos_theta) = (PI * v).sin_cos();
+    let dpdu = 2. * PI * radius * Vec3::new(sin_theta * sin_phi, 0., sin_theta * cos_phi);
+    let dpdv = PI * radius * Vec3::new(-cos_theta * cos_phi, sin_theta, cos_theta * sin_phi);
+    (dpdu, dpdv)
+}
+
+// Maps a point on the unit sphere to (u, v) in [0, 1]; u goes around the y axis starting at -x,
+// v goes from the bottom pole to the top pole
+pub fn sphere_uv(point: Vec3) -> (f64, f64) {
+    let theta = (-point.y).clamp(-1., 1.).acos();
+    let phi = f64::atan2(-point.z, point.x) + PI;
+    (phi / (2. * PI), theta / PI)
+}
+
+// UVs for surfaces without a parameterization of their own: `sphere_uv` of the direction from
+// `center` to `location`, with the tangents of the surface point seen along that direction.
+// `normal` is the surface normal at `location`.
+pub(crate) fn projected_sphere_uv(center: Point3, location: Point3, normal: Vec3) -> ((f64, f64), (Vec3, Vec3)) {
+    let offset = location - center;
+    let distance = offset.length();
+    let direction = offset / distance;
+    let facing = Vec3::dot(&normal, &direction);
+    if distance < 1e-12 || facing.abs() < 1e-12 {
+        // Seen edge on (or from inside the surface) the mapping has no derivatives
+        return ((0., 0.), normal.orthonormal_basis());
+    }
+    let (u, v) = sphere_uv(direction);
+    // Turning the direction moves the point around the sphere through it, then along the direction
+    // until it is back on the surface
+    let (ddu, ddv) = sphere_tangents(1., u, v);
+    let onto_surface = |tangent: Vec3| distance * (tangent - Vec3::dot(&normal, &tangent) / facing * direction);
+    ((u, v), (onto_surface(ddu), onto_surface(ddv)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::sphere::{MovingSphere, Sphere};
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn moving_sphere_is_hit_where_it_is_at_the_ray_time() {
+        let sphere = MovingSphere::new(
+            Vec3::new(0., 0., -5.),
+            Vec3::new(4., 0., -5.),
+            Interval::new(0., 1.),
+            1.,
+            Arc::new(Lambertian::new(Color::white())),
+        );
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let at = |x: f64, time: f64| Ray::with_time(Vec3::new(x, 0., 0.), Vec3::new(0., 0., -1.), time);
+
+        assert!(sphere.hit(&at(0., 0.), interval).is_some());
+        assert!(sphere.hit(&at(0., 1.), interval).is_none());
+        assert!(sphere.hit(&at(2., 0.5), interval).is_some());
+
+        let bounds = sphere.bounding_box().unwrap();
+        assert_eq!(bounds.min.x, -1.);
+        assert_eq!(bounds.max.x, 5.);
+    }
+
+    #[test]
+    fn tangents_match_the_uv_mapping() {
+        let sphere = Sphere::new(Vec3::zero(), 2., Arc::new(Lambertian::new(Color::white())));
+        let hit = sphere
+            .hit(&Ray::new(Vec3::new(1., 0.5, 5.), Vec3::new(0., 0., -1.)), Interval::new(0.001, f64::INFINITY))
+            .unwrap();
 
-        let t = root;
-        let location = ray.at(t);
-        let normal = (location - self.center) / self.radius;
-        Some(Hit { location, normal, t, material: self.material.clone() })
+        // Step a little in u and v and compare with where the mapping actually lands
+        let epsilon = 1e-6;
+        let point_at = |u: f64, v: f64| {
+            let (phi, theta) = (2. * PI * u, PI * v);
+            2. * Vec3::new(-theta.sin() * phi.cos(), -theta.cos(), theta.sin() * phi.sin())
+        };
+        assert!((point_at(hit.u, hit.v) - hit.location).length() < 1e-9);
+        let dpdu = (point_at(hit.u + epsilon, hit.v) - hit.location) / epsilon;
+        let dpdv = (point_at(hit.u, hit.v + epsilon) - hit.location) / epsilon;
+        assert!((dpdu - hit.dpdu).length() < 1e-4);
+        assert!((dpdv - hit.dpdv).length() < 1e-4);
+        assert!(Vec3::dot(&Vec3::cross(&hit.dpdu, &hit.dpdv), &hit.normal) > 0.);
     }
 }
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+use crate::color::Color;
+use crate::hittable::Hit;
+use crate::vec3::Point3;
+
+// Spatially varying color, looked up by surface coordinates and/or position
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, location: Point3) -> Color;
+}
+
+impl Texture for Color {
+    fn value(&self, _u: f64, _v: f64, _location: Point3) -> Color {
+        *self
+    }
+}
+
+impl<F: Fn(f64, f64, Point3) -> Color + Send + Sync> Texture for F {
+    fn value(&self, u: f64, v: f64, location: Point3) -> Color {
+        self(u, v, location)
+    }
+}
+
+// Material parameter that is either a constant or driven by a texture.
+// Scalar inputs read the texture's first channel.
+#[derive(Clone)]
+pub enum Input<T> {
+    Constant(T),
+    Texture(Arc<dyn Texture>),
+}
+
+impl Input<Color> {
+    pub fn evaluate(&self, hit: &Hit) -> Color {
+        match self {
+            Input::Constant(color) => *color,
+            Input::Texture(texture) => texture.value(hit.u, hit.v, hit.location),
+        }
+    }
+}
+
+impl Input<f64> {
+    pub fn evaluate(&self, hit: &Hit) -> f64 {
+        match self {
+            Input::Constant(value) => *value,
+            Input::Texture(texture) => texture.value(hit.u, hit.v, hit.location).x,
+        }
+    }
+}
+
+impl<T> From<T> for Input<T> {
+    fn from(value: T) -> Self {
+        Input::Constant(value)
+    }
+}
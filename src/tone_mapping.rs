@@ -0,0 +1,200 @@
+use crate::color::{linear_to_srgb, srgb_to_linear, Color};
+use crate::vec3::Vec3;
+
+// Curves that squeeze HDR radiance into the [0, 1] range a display can show
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ToneMapper {
+    // Clips everything above 1
+    #[default]
+    Clamp,
+    // x / (1 + x) per channel; never clips, but flattens highlights and desaturates little
+    Reinhard,
+    // Stephen Hill's fit of the ACES reference and output transforms, a contrasty film look
+    AcesFilmic,
+    // Troy Sobotka's AgX (as in Blender), which desaturates bright colors toward white like film does
+    Agx,
+    // John Hable's filmic curve from Uncharted 2
+    Hable,
+}
+
+impl ToneMapper {
+    // Maps linear scene light to linear display light in [0, 1]
+    pub fn apply(&self, color: Color) -> Color {
+        let mapped = match self {
+            ToneMapper::Clamp => color,
+            ToneMapper::Reinhard => color.map(|c| c.max(0.) / (1. + c.max(0.))),
+            ToneMapper::AcesFilmic => aces_filmic(color),
+            ToneMapper::Agx => agx(color),
+            ToneMapper::Hable => {
+                let white_scale = 1. / hable_partial(HABLE_WHITE);
+                color.map(|c| hable_partial(2. * c.max(0.)) * white_scale)
+            }
+        };
+        mapped.clamp(0., 1.)
+    }
+}
+
+fn aces_filmic(color: Color) -> Color {
+    // sRGB to the ACES rendering space, with the RRT's saturation adjustment folded in
+    let input = [
+        Vec3::new(0.59719, 0.35458, 0.04823),
+        Vec3::new(0.07600, 0.90834, 0.01566),
+        Vec3::new(0.02840, 0.13383, 0.83777),
+    ];
+    let output = [
+        Vec3::new(1.60475, -0.53108, -0.07367),
+        Vec3::new(-0.10208, 1.10813, -0.00605),
+        Vec3::new(-0.00327, -0.07276, 1.07602),
+    ];
+    let v = multiply(&input, color);
+    let v = v.map(|c| (c * (c + 0.0245786) - 0.000090537) / (c * (0.983729 * c + 0.4329510) + 0.238081));
+    multiply(&output, v)
+}
+
+fn agx(color: Color) -> Color {
+    // Inset into the AgX working space, where the curve is applied to log2 exposure
+    let inset = [
+        Vec3::new(0.842479062253094, 0.0784335999999992, 0.0792237451477643),
+        Vec3::new(0.0423282422610123, 0.878468636469772, 0.0791661274605434),
+        Vec3::new(0.0423756549057051, 0.0784336, 0.879142973793104),
+    ];
+    let outset = [
+        Vec3::new(1.19687900512017, -0.0980208811401368, -0.0990297440797205),
+        Vec3::new(-0.0528968517574562, 1.15190312990417, -0.0989611768448433),
+        Vec3::new(-0.0529716355144438, -0.0980434501171241, 1.15107367264116),
+    ];
+    let (min_ev, max_ev) = (-12.47393, 4.026069);
+
+    let v = multiply(&inset, color).map(|c| {
+        let ev = c.max(1e-10).log2().clamp(min_ev, max_ev);
+        agx_contrast((ev - min_ev) / (max_ev - min_ev))
+    });
+    // The curve produces display-encoded values; decode them so the output is linear like the other operators
+    multiply(&outset, v).map(srgb_to_linear)
+}
+
+// Polynomial fit of the AgX base contrast curve
+fn agx_contrast(x: f64) -> f64 {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232
+}
+
+// Linear scene value that maps to display white
+const HABLE_WHITE: f64 = 11.2;
+
+fn hable_partial(x: f64) -> f64 {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f
+}
+
+fn multiply(rows: &[Vec3; 3], v: Vec3) -> Vec3 {
+    Vec3::new(Vec3::dot(&rows[0], &v), Vec3::dot(&rows[1], &v), Vec3::dot(&rows[2], &v))
+}
+
+// Everything between the linear radiance `Camera::render` produces and the bytes of an 8-bit image
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct OutputTransform {
+    pub exposure: f64, // In EV stops; every +1 doubles the brightness
+    pub tone_mapper: ToneMapper,
+    // Ordered dithering when quantizing to 8 bits, which breaks up banding in smooth gradients
+    pub dither: bool,
+}
+
+impl OutputTransform {
+    pub fn new(exposure: f64, tone_mapper: ToneMapper) -> OutputTransform {
+        OutputTransform { exposure, tone_mapper, dither: false }
+    }
+
+    pub fn with_dither(mut self, dither: bool) -> OutputTransform {
+        self.dither = dither;
+        self
+    }
+
+    // sRGB-encoded display color in [0, 1]
+    pub fn display(&self, linear: Color) -> Color {
+        let exposed = linear * self.exposure.exp2();
+        self.tone_mapper.apply(exposed).map(linear_to_srgb)
+    }
+
+    // 8-bit display color of the pixel at (x, y); the position only matters for dithering
+    pub fn quantize(&self, linear: Color, x: u16, y: u16) -> [u8; 3] {
+        let threshold = if self.dither {
+            BAYER[y as usize % 4][x as usize % 4] as f64 / 16. + 1. / 32.
+        } else {
+            0.5
+        };
+        let display = self.display(linear);
+        [display.x, display.y, display.z].map(|c| (c * 255. + threshold).floor().clamp(0., 255.) as u8)
+    }
+}
+
+// 4x4 Bayer matrix; its thresholds are spread so that any flat area averages to the exact value
+const BAYER: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::color::{linear_to_srgb, srgb_to_linear, Color};
+    use crate::tone_mapping::{OutputTransform, ToneMapper};
+
+    const OPERATORS: [ToneMapper; 5] =
+        [ToneMapper::Clamp, ToneMapper::Reinhard, ToneMapper::AcesFilmic, ToneMapper::Agx, ToneMapper::Hable];
+
+    #[test]
+    fn srgb_transfer_function() {
+        assert_eq!(linear_to_srgb(0.), 0.);
+        assert!((linear_to_srgb(1.) - 1.).abs() < 1e-12);
+        assert!((linear_to_srgb(0.5) - 0.735357).abs() < 1e-6);
+        for value in [0.001, 0.02, 0.3, 0.9] {
+            assert!((srgb_to_linear(linear_to_srgb(value)) - value).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn operators_are_monotonic_and_stay_in_range() {
+        for operator in OPERATORS {
+            let mut previous = -1.;
+            for i in 0..200 {
+                let value = operator.apply(Color::new(1., 1., 1.) * (i as f64 * 0.1)).y;
+                assert!((0. ..=1.).contains(&value), "{operator:?}");
+                assert!(value >= previous - 1e-9, "{operator:?} at {i}");
+                previous = value;
+            }
+            assert!(operator.apply(Color::black()).y < 0.01, "{operator:?}");
+        }
+    }
+
+    #[test]
+    fn highlights_are_compressed_instead_of_clipped() {
+        for operator in [ToneMapper::Reinhard, ToneMapper::AcesFilmic, ToneMapper::Agx, ToneMapper::Hable] {
+            let bright = operator.apply(Color::new(4., 4., 4.)).y;
+            let brighter = operator.apply(Color::new(8., 8., 8.)).y;
+            assert!(bright < brighter, "{operator:?}");
+        }
+        assert!((ToneMapper::Hable.apply(Color::white() * 5.6).y - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn exposure_is_in_stops() {
+        let one_stop_up = OutputTransform::new(1., ToneMapper::Clamp);
+        let display = one_stop_up.display(Color::white() * 0.25);
+        assert!((display.x - linear_to_srgb(0.5)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dithering_averages_to_the_exact_value() {
+        // Encodes to 100.25 / 255, so a quarter of the pixels should round up
+        let linear = srgb_to_linear(100.25 / 255.);
+        let transform = OutputTransform::default().with_dither(true);
+        let values: Vec<u8> = (0..4).flat_map(|y| (0..4).map(move |x| (x, y))).map(|(x, y)| transform.quantize(Color::white() * linear, x, y)[0]).collect();
+        assert_eq!(values.iter().filter(|&&value| value == 101).count(), 4);
+        assert_eq!(values.iter().filter(|&&value| value == 100).count(), 12);
+
+        assert_eq!(OutputTransform::default().quantize(Color::white() * linear, 0, 0), [100; 3]);
+    }
+}
@@ -67,6 +67,17 @@ impl Vec3 {
         }
     }
 
+    // Two unit vectors that together with this (unit) vector form a right-handed orthonormal basis.
+    // Branchless construction from Duff et al. 2017, "Building an Orthonormal Basis, Revisited".
+    pub fn orthonormal_basis(&self) -> (Vec3, Vec3) {
+        let sign = 1f64.copysign(self.z);
+        let a = -1. / (sign + self.z);
+        let b = self.x * self.y * a;
+        let tangent = Vec3::new(1. + sign * self.x * self.x * a, sign * b, -sign * self.x);
+        let bitangent = Vec3::new(b, sign + self.y * self.y * a, -self.y);
+        (tangent, bitangent)
+    }
+
     pub fn reflect(&self, surface_normal: Vec3) -> Vec3 {
         self - 2. * Vec3::dot(self, &surface_normal) * surface_normal
     }
@@ -276,11 +287,12 @@ impl ops::Neg for Vec3 {
 impl Distribution<Vec3> for StandardUniform {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec3 {
         loop {
-            let x: f64 = rng.random_range(0.0..=1.0);
-            let y: f64 = rng.random_range(0.0..=1.0);
-            let z: f64 = rng.random_range(0.0..=1.0);
+            let x: f64 = rng.random_range(-1.0..=1.0);
+            let y: f64 = rng.random_range(-1.0..=1.0);
+            let z: f64 = rng.random_range(-1.0..=1.0);
             let vec3 = Vec3 { x, y, z };
-            if vec3.length_squared() <= 1. {
+            // Rejecting points outside the ball (and too close to its center to normalize) keeps the directions uniform
+            if 1e-160 < vec3.length_squared() && vec3.length_squared() <= 1. {
                 return vec3.normalize();
             }
         }
@@ -289,8 +301,18 @@ impl Distribution<Vec3> for StandardUniform {
 
 #[cfg(test)]
 mod tests {
+    use rand::distr::StandardUniform;
+    use rand::{rng, Rng};
     use crate::vec3::Vec3;
 
+    #[test]
+    fn random_unit_vectors_cover_every_direction() {
+        let samples: Vec<Vec3> = rng().sample_iter(StandardUniform).take(1000).collect();
+        let mean: Vec3 = samples.iter().copied().sum::<Vec3>() / samples.len() as f64;
+        assert!(mean.length() < 0.15);
+        assert!(samples.iter().all(|sample| (sample.length() - 1.).abs() < 1e-9));
+    }
+
     #[test]
     fn reflect_basic() {
         let a = Vec3::new(1., -1., 3.);
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+use rand::{rng, Rng};
+use crate::aabb::Aabb;
+use crate::color::Color;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::materials::isotropic::Isotropic;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// Uniform fog or smoke filling a closed boundary shape. A ray passing through scatters after an
+// exponentially distributed distance; the scattering point is reported as a hit with the phase
+// function as its material, so the renderer treats it like any other bounce.
+#[derive(Clone)]
+pub struct ConstantMedium {
+    pub boundary: Arc<dyn Hittable>,
+    pub density: f64,
+    pub phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Arc<dyn Hittable>, density: f64, albedo: Color) -> ConstantMedium {
+        ConstantMedium { boundary, density, phase_function: Arc::new(Isotropic::new(albedo)) }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        // Find where the ray's line enters and leaves the boundary, even if the ray starts inside it
+        let enter = self.boundary.hit(ray, Interval::UNIVERSE)?;
+        let exit = self.boundary.hit(ray, Interval::new(enter.t + 0.0001, f64::INFINITY))?;
+
+        let start = enter.t.max(t_interval.min).max(0.);
+        let end = exit.t.min(t_interval.max);
+        if start >= end {
+            return None;
+        }
+
+        let ray_length = ray.direction.length();
+        let distance_inside = (end - start) * ray_length;
+        let hit_distance = -rng().random::<f64>().ln() / self.density;
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = start + hit_distance / ray_length;
+        Some(Hit::new(
+            ray,
+            t,
+            Vec3::new(1., 0., 0.), // Arbitrary, a scattering event has no surface
+            0.,
+            0.,
+            self.phase_function.clone(),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::color::Color;
+    use crate::hittable::Hittable;
+    use crate::interval::Interval;
+    use crate::materials::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use crate::sphere::Sphere;
+    use crate::vec3::Vec3;
+    use crate::volumes::constant_medium::ConstantMedium;
+
+    fn fog(density: f64) -> ConstantMedium {
+        let boundary = Sphere::new(Vec3::zero(), 1., Arc::new(Lambertian::new(Color::white())));
+        ConstantMedium::new(Arc::new(boundary), density, Color::white())
+    }
+
+    #[test]
+    fn dense_medium_scatters_right_at_the_boundary() {
+        let ray = Ray::new(Vec3::new(0., 0., 5.), Vec3::new(0., 0., -1.));
+        let hit = fog(1e9).hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 4.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ray_starting_inside_scatters_ahead_of_its_origin() {
+        let ray = Ray::new(Vec3::zero(), Vec3::new(0., 1., 0.));
+        let hit = fog(1e9).hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        assert!((hit.t - 0.001).abs() < 1e-6);
+    }
+
+    #[test]
+    fn thin_medium_lets_rays_through() {
+        let ray = Ray::new(Vec3::new(0., 0., 5.), Vec3::new(0., 0., -1.));
+        assert!(fog(1e-12).hit(&ray, Interval::new(0.001, f64::INFINITY)).is_none());
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+use rand::{rng, Rng};
+use crate::aabb::Aabb;
+use crate::color::Color;
+use crate::hittable::{Hit, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::materials::henyey_greenstein::HenyeyGreenstein;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::volumes::voxel_grid::VoxelGrid;
+
+// Heterogeneous medium such as a cloud, with densities from a voxel grid stretched over `bounds`.
+// Collisions are found by delta tracking against the grid's maximum density, which is unbiased
+// however the density varies in between.
+#[derive(Clone)]
+pub struct GridMedium {
+    pub grid: Arc<VoxelGrid>,
+    pub bounds: Aabb,
+    pub density_scale: f64,
+    pub phase_function: Arc<dyn Material>,
+}
+
+impl GridMedium {
+    pub fn new(grid: Arc<VoxelGrid>, bounds: Aabb, density_scale: f64, albedo: Color, g: f64) -> GridMedium {
+        GridMedium {
+            grid,
+            bounds,
+            density_scale,
+            phase_function: Arc::new(HenyeyGreenstein::new(albedo, g)),
+        }
+    }
+
+    pub fn density(&self, point: Point3) -> f64 {
+        let local = (point - self.bounds.min) / (self.bounds.max - self.bounds.min);
+        self.density_scale * self.grid.density(local)
+    }
+
+    fn majorant(&self) -> f64 {
+        self.density_scale * self.grid.max_density()
+    }
+
+    // Visits tentative collisions between `start` and `end`, spaced as if the whole medium had the
+    // majorant density, until `visit` returns false. Returns the t of the collision it stopped at.
+    fn track(&self, ray: &Ray, start: f64, end: f64, mut visit: impl FnMut(f64) -> bool) -> Option<f64> {
+        let majorant = self.majorant();
+        if majorant <= 0. {
+            return None;
+        }
+        let ray_length = ray.direction.length();
+        let mut t = start;
+        loop {
+            t -= (1. - rng().random::<f64>()).ln() / (majorant * ray_length);
+            if t >= end {
+                return None;
+            }
+            if !visit(t) {
+                return Some(t);
+            }
+        }
+    }
+
+    // Unbiased estimate of the fraction of light that makes it along the ray through `t_interval`,
+    // by ratio tracking. Useful for shadow rays, where a 0/1 answer from delta tracking is noisier.
+    pub fn transmittance(&self, ray: &Ray, t_interval: Interval) -> f64 {
+        let Some(clipped) = self.bounds.clip(ray, t_interval) else {
+            return 1.;
+        };
+        let majorant = self.majorant();
+        let mut transmittance = 1.;
+        self.track(ray, clipped.min, clipped.max, |t| {
+            transmittance *= 1. - self.density(ray.at(t)) / majorant;
+            true
+        });
+        transmittance
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<Hit> {
+        let clipped = self.bounds.clip(ray, t_interval)?;
+        let majorant = self.majorant();
+
+        // A tentative collision is real with probability density / majorant, otherwise it's null
+        let t = self.track(ray, clipped.min, clipped.max, |t| {
+            rng().random::<f64>() * majorant >= self.density(ray.at(t))
+        })?;
+        Some(Hit::new(
+            ray,
+            t,
+            Vec3::new(1., 0., 0.), // Arbitrary, a scattering event has no surface
+            0.,
+            0.,
+            self.phase_function.clone(),
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::aabb::Aabb;
+    use crate::color::Color;
+    use crate::interval::Interval;
+    use crate::ray::Ray;
+    use crate::vec3::Vec3;
+    use crate::volumes::grid_medium::GridMedium;
+    use crate::volumes::voxel_grid::VoxelGrid;
+
+    #[test]
+    fn ratio_tracking_matches_beer_lambert() {
+        // Half the voxels are empty, so the majorant is twice the mean density along the ray
+        let densities = (0..64).map(|i| if i % 2 == 0 { 1. } else { 0. }).collect();
+        let grid = Arc::new(VoxelGrid::new([4, 4, 4], densities));
+        let bounds = Aabb::new(Vec3::zero(), Vec3::new(2., 2., 2.));
+        let medium = GridMedium::new(grid, bounds, 0.5, Color::white(), 0.);
+
+        // Along y = z = 1/8 the x profile alternates 1, 0, 1, 0 between voxel centers
+        let ray = Ray::new(Vec3::new(-1., 0.25, 0.25), Vec3::new(1., 0., 0.));
+        let optical_depth: f64 = (0..10000)
+            .map(|i| 2. * (i as f64 + 0.5) / 10000.)
+            .map(|x| medium.density(Vec3::new(x, 0.25, 0.25)) * 2. / 10000.)
+            .sum();
+
+        let samples = 50000;
+        let estimate = (0..samples)
+            .map(|_| medium.transmittance(&ray, Interval::new(0., f64::INFINITY)))
+            .sum::<f64>() / samples as f64;
+        assert!((estimate - (-optical_depth).exp()).abs() < 0.01, "{estimate}");
+    }
+}
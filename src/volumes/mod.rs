@@ -0,0 +1,3 @@
+pub mod constant_medium;
+pub mod grid_medium;
+pub mod voxel_grid;
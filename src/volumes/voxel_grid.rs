@@ -0,0 +1,155 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use crate::vec3::Point3;
+
+// Dense 3D grid of densities covering the unit cube, with samples at the voxel centers.
+// x varies fastest in `densities`, then y, then z.
+#[derive(Clone, Debug)]
+pub struct VoxelGrid {
+    resolution: [usize; 3],
+    densities: Vec<f32>,
+    max_density: f64,
+}
+
+impl VoxelGrid {
+    pub fn new(resolution: [usize; 3], densities: Vec<f32>) -> VoxelGrid {
+        assert!(resolution.iter().all(|&n| n > 0), "a voxel grid needs at least one voxel along each axis");
+        assert_eq!(resolution.iter().product::<usize>(), densities.len(), "densities must fill the grid");
+        let max_density = densities.iter().copied().fold(0., f32::max) as f64;
+        VoxelGrid { resolution, densities, max_density }
+    }
+
+    // Reads the grid file format: three little-endian u32 dimensions followed by that many
+    // little-endian f32 densities in x-fastest order
+    pub fn read(reader: &mut impl Read) -> io::Result<VoxelGrid> {
+        let mut read_u32 = || -> io::Result<u32> {
+            let mut bytes = [0; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        };
+        let resolution = [read_u32()? as usize, read_u32()? as usize, read_u32()? as usize];
+        if resolution.contains(&0) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "voxel grid has a zero dimension"));
+        }
+        let size = resolution
+            .iter()
+            .try_fold(4usize, |size, &n| size.checked_mul(n))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "voxel grid is too large"))?;
+
+        // Grows with what is actually there, so a bogus header can't make us allocate its full size up front
+        let mut bytes = Vec::new();
+        reader.take(size as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != size {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "voxel grid data is truncated"));
+        }
+        let densities = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        Ok(VoxelGrid::new(resolution, densities))
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        for n in self.resolution {
+            writer.write_all(&(n as u32).to_le_bytes())?;
+        }
+        for density in &self.densities {
+            writer.write_all(&density.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<VoxelGrid> {
+        VoxelGrid::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    pub fn max_density(&self) -> f64 {
+        self.max_density
+    }
+
+    pub fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        let [nx, ny, _] = self.resolution;
+        self.densities[(z * ny + y) * nx + x] as f64
+    }
+
+    // Trilinearly interpolated density at a point of the unit cube; zero outside it
+    pub fn density(&self, point: Point3) -> f64 {
+        if !(0. ..=1.).contains(&point.x) || !(0. ..=1.).contains(&point.y) || !(0. ..=1.).contains(&point.z) {
+            return 0.;
+        }
+        // Continuous voxel coordinates, with voxel centers on whole numbers
+        let [nx, ny, nz] = self.resolution;
+        let axis = |p: f64, n: usize| {
+            let position = (p * n as f64 - 0.5).clamp(0., (n - 1) as f64);
+            let low = (position.floor() as usize).min(n.saturating_sub(2));
+            let high = (low + 1).min(n - 1);
+            (low, high, position - low as f64)
+        };
+        let (x0, x1, fx) = axis(point.x, nx);
+        let (y0, y1, fy) = axis(point.y, ny);
+        let (z0, z1, fz) = axis(point.z, nz);
+
+        let lerp = |a: f64, b: f64, f: f64| a + (b - a) * f;
+        let along_x = |y: usize, z: usize| lerp(self.voxel(x0, y, z), self.voxel(x1, y, z), fx);
+        let along_y = |z: usize| lerp(along_x(y0, z), along_x(y1, z), fy);
+        lerp(along_y(z0), along_y(z1), fz)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vec3::Vec3;
+    use crate::volumes::voxel_grid::VoxelGrid;
+
+    #[test]
+    fn trilinear_interpolation_between_voxel_centers() {
+        let grid = VoxelGrid::new([2, 1, 1], vec![0., 2.]);
+        assert_eq!(grid.density(Vec3::new(0.25, 0.5, 0.5)), 0.);
+        assert_eq!(grid.density(Vec3::new(0.5, 0.5, 0.5)), 1.);
+        assert_eq!(grid.density(Vec3::new(0.9, 0.5, 0.5)), 2.);
+        assert_eq!(grid.density(Vec3::new(1.5, 0.5, 0.5)), 0.);
+    }
+
+    #[test]
+    fn file_format_round_trip() {
+        let grid = VoxelGrid::new([1, 2, 3], vec![0., 1., 2., 3., 4., 5.5]);
+        let mut bytes = Vec::new();
+        grid.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 12 + 6 * 4);
+
+        let read = VoxelGrid::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.resolution(), [1, 2, 3]);
+        assert_eq!(read.voxel(0, 1, 2), 5.5);
+        assert_eq!(read.max_density(), 5.5);
+    }
+
+    #[test]
+    fn rejects_oversized_and_truncated_files() {
+        let mut huge = Vec::new();
+        for n in [u32::MAX; 3] {
+            huge.extend_from_slice(&n.to_le_bytes());
+        }
+        let error = VoxelGrid::read(&mut huge.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut truncated = Vec::new();
+        for n in [1000u32, 1000, 1000] {
+            truncated.extend_from_slice(&n.to_le_bytes());
+        }
+        truncated.extend_from_slice(&1f32.to_le_bytes());
+        let error = VoxelGrid::read(&mut truncated.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}